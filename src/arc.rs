@@ -1,7 +1,9 @@
 use alloc::alloc::handle_alloc_error;
-use alloc::boxed::Box;
+use allocator_api2::alloc::{Allocator, Global};
+use allocator_api2::boxed::Box;
 use backdrop::Backdrop;
 use core::alloc::Layout;
+use core::any::Any;
 use core::borrow;
 use core::cmp::Ordering;
 use core::convert::From;
@@ -15,7 +17,6 @@ use core::ops::Deref;
 use core::ptr::{self, NonNull};
 use core::sync::atomic;
 use core::sync::atomic::Ordering::{Acquire, Relaxed, Release};
-use core::{isize, usize};
 
 extern crate backdrop;
 use self::backdrop::BackdropStrategy;
@@ -25,7 +26,7 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "stable_deref_trait")]
 use stable_deref_trait::{CloneStableDeref, StableDeref};
 
-use crate::{abort, ArcBorrow, HeaderSlice, OffsetArc, UniqueArc};
+use crate::{abort, ArcBorrow, HeaderSlice, OffsetArc, ThinPointee, UniqueArc};
 
 /// A soft limit on the amount of references that may be made to an `Arc`.
 ///
@@ -38,7 +39,10 @@ const MAX_REFCOUNT: usize = (isize::MAX) as usize;
 /// (The structure which contains the reference count and `T` itself.)
 ///
 /// Its internals are hidden, but the type is made public
-/// because you will receive a `Box<ArcInner<T>>` when backdropping.
+/// because you will receive a `Box<ArcInner<T>, A>` when backdropping.
+///
+/// (This is an [`allocator_api2::boxed::Box`], so that the memory is returned
+/// to the right allocator, even when dropped on another thread.)
 #[derive(Debug)]
 #[repr(C)]
 pub struct ArcInner<T: ?Sized> {
@@ -49,6 +53,24 @@ pub struct ArcInner<T: ?Sized> {
 unsafe impl<T: ?Sized + Sync + Send> Send for ArcInner<T> {}
 unsafe impl<T: ?Sized + Sync + Send> Sync for ArcInner<T> {}
 
+/// The error returned by the fallible (`try_*`) constructors
+/// when the memory allocator reports failure,
+/// or when the requested size would overflow the maximum allocation size.
+///
+/// The infallible constructors (`Arc::new`, `Arc::from_header_and_iter`, etc.)
+/// call [`handle_alloc_error`](alloc::alloc::handle_alloc_error) instead, which usually aborts the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AllocError {}
+
 /// An atomically reference counted shared pointer
 ///
 /// See the documentation for [`Arc`] in the standard library. Unlike the
@@ -78,20 +100,44 @@ unsafe impl<T: ?Sized + Sync + Send> Sync for ArcInner<T> {}
 ///
 /// See [`backdrop::Backdrop`] for more info.
 ///
+/// # Custom allocators
+///
+/// The optional third type parameter `A` is the [`Allocator`] the `ArcInner` lives in.
+/// It defaults to the [`Global`] allocator.
+/// Use [`Arc::new_in`] to construct an `Arc` in another allocator (such as an arena or a tracking allocator).
+/// The strategy receives a `Box<ArcInner<T>, A>`,
+/// so the memory is always returned to the allocator it came from,
+/// even when it is dropped on a background thread.
+///
+/// Note that this `Box` is [`allocator_api2::boxed::Box`](crate::allocator_api2::boxed::Box),
+/// not the standard library one; custom strategies need to be implemented for it.
+///
+/// # Layout
+///
+/// An `Arc` is a pointer to its `ArcInner` followed by its allocator.
+/// For zero-sized allocators (such as [`Global`]) it is exactly one pointer in size,
+/// but it is not `#[repr(transparent)]`, and with other allocators it is larger.
+///
 /// [`Arc`]: https://doc.rust-lang.org/stable/std/sync/struct.Arc.html
-#[repr(transparent)]
-pub struct Arc<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> {
+pub struct Arc<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>, A>>, A: Allocator = Global> {
     pub(crate) p: ptr::NonNull<ArcInner<T>>,
     pub(crate) phantom: PhantomData<T>,
     pub(crate) phantom_strategy: PhantomData<S>,
+    pub(crate) alloc: ManuallyDrop<A>,
 }
 
-unsafe impl<T: ?Sized + Sync + Send, S> Send for Arc<T, S> where
-    S: BackdropStrategy<Box<ArcInner<T>>>
+// An `Arc` in a zero-sized allocator is still a single pointer.
+const _: () = assert!(
+    core::mem::size_of::<Arc<(), self::backdrop::TrivialStrategy>>()
+        == core::mem::size_of::<*const ()>()
+);
+
+unsafe impl<T: ?Sized + Sync + Send, S, A: Allocator + Send> Send for Arc<T, S, A> where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>
 {
 }
-unsafe impl<T: ?Sized + Sync + Send, S> Sync for Arc<T, S> where
-    S: BackdropStrategy<Box<ArcInner<T>>>
+unsafe impl<T: ?Sized + Sync + Send, S, A: Allocator + Sync> Sync for Arc<T, S, A> where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>
 {
 }
 
@@ -99,25 +145,20 @@ impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
     /// Construct an `Arc<T, S>`
     #[inline]
     pub fn new(data: T) -> Self {
-        let ptr = Box::into_raw(Box::new(ArcInner {
-            count: atomic::AtomicUsize::new(1),
-            data,
-        }));
-
-        unsafe {
-            Arc {
-                p: ptr::NonNull::new_unchecked(ptr),
-                phantom: PhantomData,
-                phantom_strategy: PhantomData,
-            }
-        }
+        Arc::new_in(data, Global)
     }
 
-    /// Alter the strategy that is used for an Arc<T, S> to another.
-    /// This is a zero-cost operation.
-    pub fn with_strategy<S2: BackdropStrategy<Box<ArcInner<T>>>>(arc: Arc<T, S>) -> Arc<T, S2> {
-        // Safety: S and S2 are ZSTs which only do something at drop-time
-        unsafe { core::mem::transmute(arc) }
+    /// Construct an `Arc<T, S>`, returning an error if allocation fails.
+    ///
+    /// ```
+    /// use backdrop_arc::{Arc, TrivialStrategy};
+    ///
+    /// let mynum: Arc<usize, TrivialStrategy> = Arc::try_new(42).unwrap();
+    /// assert_eq!(*mynum, 42);
+    /// ```
+    #[inline]
+    pub fn try_new(data: T) -> Result<Self, AllocError> {
+        Arc::try_new_in(data, Global)
     }
 
     /// Reconstruct the Arc<T, S> from a raw pointer obtained from into_raw()
@@ -126,6 +167,11 @@ impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
     /// by the atomic count.
     ///
     /// It is recommended to use OffsetArc for this
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been obtained from `Arc::<T, S>::into_raw`,
+    /// and the reference it represents must not be used again afterwards.
     #[inline]
     pub unsafe fn from_raw(ptr: *const T) -> Self {
         // FIXME: when `byte_sub` is stabilized, this can accept T: ?Sized.
@@ -135,7 +181,9 @@ impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
         let ptr = (ptr as *const u8).sub(offset_of!(ArcInner<T>, data));
         Arc::from_raw_inner(ptr as *mut ArcInner<T>)
     }
+}
 
+impl<T: ?Sized + ThinPointee, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
     /// Temporarily converts |self| into a bonafide OffsetArc and exposes it to the
     /// provided callback. The refcount is not modified.
     #[inline(always)]
@@ -143,6 +191,9 @@ impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
     where
         F: FnOnce(&OffsetArc<T, S>) -> U,
     {
+        // Check up front, so that `into_raw_offset` can't panic while holding the copied `Arc`.
+        T::assert_thin(self);
+
         // Synthesize transient Arc, which never touches the refcount of the ArcInner.
         // Store transient in `ManuallyDrop`, to leave the refcount untouched.
         let transient = unsafe { ManuallyDrop::new(Arc::into_raw_offset(ptr::read(self))) };
@@ -153,11 +204,18 @@ impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
 
     /// Converts an `Arc` into a `OffsetArc`. This consumes the `Arc`, so the refcount
     /// is not modified.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is a `HeaderSlice<HeaderWithLength<H>, [T]>` whose header length
+    /// does not match the length of its slice.
     #[inline]
     pub fn into_raw_offset(a: Self) -> OffsetArc<T, S> {
+        T::assert_thin(&a);
         unsafe {
+            let data = (Arc::into_raw_inner(a) as *mut u8).add(T::data_offset());
             OffsetArc {
-                ptr: ptr::NonNull::new_unchecked(Arc::into_raw(a) as *mut T),
+                ptr: ptr::NonNull::new_unchecked(data as *mut ()),
                 phantom: PhantomData,
                 phantom_strategy: PhantomData,
             }
@@ -169,8 +227,65 @@ impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
     #[inline]
     pub fn from_raw_offset(a: OffsetArc<T, S>) -> Self {
         let a = ManuallyDrop::new(a);
-        let ptr = a.ptr.as_ptr();
-        unsafe { Arc::from_raw(ptr) }
+        unsafe { Arc::from_raw_inner(a.inner()) }
+    }
+}
+
+impl<T, S: BackdropStrategy<Box<ArcInner<T>, A>>, A: Allocator> Arc<T, S, A> {
+    /// Construct an `Arc<T, S, A>` whose `ArcInner` is allocated in `alloc`.
+    ///
+    /// ```
+    /// use backdrop_arc::{Arc, Global, TrivialStrategy};
+    ///
+    /// let mynum: Arc<usize, TrivialStrategy, Global> = Arc::new_in(42, Global);
+    /// assert_eq!(*mynum, 42);
+    /// ```
+    #[inline]
+    pub fn new_in(data: T, alloc: A) -> Self {
+        // Safety: the closure only changes the type of the pointer
+        let inner = unsafe {
+            Arc::<_, S, A>::allocate_for_layout(Layout::new::<T>(), &alloc, |mem| {
+                mem as *mut ArcInner<T>
+            })
+        };
+
+        // Safety: `inner` was just allocated with room for a `T`
+        unsafe { Arc::write_data_in(inner, data, alloc) }
+    }
+
+    /// Construct an `Arc<T, S, A>` whose `ArcInner` is allocated in `alloc`,
+    /// returning an error if allocation fails.
+    #[inline]
+    pub fn try_new_in(data: T, alloc: A) -> Result<Self, AllocError> {
+        // Safety: the closure only changes the type of the pointer
+        let inner = unsafe {
+            Arc::<_, S, A>::try_allocate_for_layout(Layout::new::<T>(), &alloc, |mem| {
+                mem as *mut ArcInner<T>
+            })?
+        };
+
+        // Safety: `inner` was just allocated with room for a `T`
+        Ok(unsafe { Arc::write_data_in(inner, data, alloc) })
+    }
+
+    /// # Safety
+    ///
+    /// `inner` must be freshly allocated in `alloc`, with an initialized refcount.
+    #[inline]
+    unsafe fn write_data_in(inner: NonNull<ArcInner<T>>, data: T, alloc: A) -> Self {
+        ptr::write(ptr::addr_of_mut!((*inner.as_ptr()).data), data);
+
+        Arc::from_raw_inner_in(inner.as_ptr(), alloc)
+    }
+
+    /// Alter the strategy that is used for an Arc<T, S> to another.
+    /// This is a zero-cost operation.
+    pub fn with_strategy<S2: BackdropStrategy<Box<ArcInner<T>, A>>>(
+        arc: Arc<T, S, A>,
+    ) -> Arc<T, S2, A> {
+        // Safety: S and S2 are ZSTs which only do something at drop-time
+        let (ptr, alloc) = Arc::into_raw_inner_with_allocator(arc);
+        unsafe { Arc::from_raw_inner_in(ptr, alloc) }
     }
 
     /// Returns the inner value, if the [`Arc`] has exactly one strong reference.
@@ -201,8 +316,13 @@ impl<T, S: BackdropStrategy<Box<ArcInner<[T]>>>> Arc<[T], S> {
     /// [`Arc::from_raw`] should accept unsized types, but this is not trivial to do correctly
     /// until the feature [`pointer_bytes_offsets`](https://github.com/rust-lang/rust/issues/96283)
     /// is stabilized. This is stopgap solution for slices.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been obtained from `Arc::<[T], S>::into_raw`,
+    /// and the reference it represents must not be used again afterwards.
     pub unsafe fn from_raw_slice(ptr: *const [T]) -> Self {
-        let len = (*ptr).len();
+        let len = (&*ptr).len();
         // Assuming the offset of `T` in `ArcInner<T>` is the same
         // as as offset of `[T]` in `ArcInner<[T]>`.
         // (`offset_of!` macro requires `Sized`.)
@@ -226,6 +346,44 @@ impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
         this.as_ptr()
     }
 
+    #[inline]
+    pub(super) fn into_raw_inner(this: Self) -> *mut ArcInner<T> {
+        let this = ManuallyDrop::new(this);
+        this.ptr()
+    }
+
+    /// Construct an `Arc` from an allocated `ArcInner`.
+    /// # Safety
+    /// The `ptr` must point to a valid instance, allocated by an `Arc`. The reference could will
+    /// not be modified.
+    pub(super) unsafe fn from_raw_inner(ptr: *mut ArcInner<T>) -> Self {
+        Arc::from_raw_inner_in(ptr, Global)
+    }
+
+    /// Produce a pointer to the data that can be converted back
+    /// to an Arc. This is basically an `&Arc<T, S>`, without the extra indirection.
+    /// It has the benefits of an `&T` but also knows about the underlying refcount
+    /// and can be converted into more `Arc<T, S>`s if necessary.
+    #[inline]
+    pub fn borrow_arc(&self) -> ArcBorrow<'_, T> {
+        ArcBorrow(&**self, PhantomData)
+    }
+
+    /// Like [`borrow_arc`](Arc::borrow_arc), but the `ArcBorrow` remembers the strategy `S`,
+    /// so it can only be converted back into an `Arc<T, S>`.
+    #[inline]
+    pub fn borrow_arc_with_strategy(&self) -> ArcBorrow<'_, T, S> {
+        ArcBorrow(&**self, PhantomData)
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>, A>>, A: Allocator> Arc<T, S, A> {
+    /// Returns a reference to the underlying allocator.
+    #[inline]
+    pub fn allocator(this: &Self) -> &A {
+        &this.alloc
+    }
+
     /// Returns the raw pointer.
     ///
     /// Same as into_raw except `self` isn't consumed.
@@ -238,15 +396,6 @@ impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
         unsafe { ptr::addr_of_mut!((*self.ptr()).data) }
     }
 
-    /// Produce a pointer to the data that can be converted back
-    /// to an Arc. This is basically an `&Arc<T, S>`, without the extra indirection.
-    /// It has the benefits of an `&T` but also knows about the underlying refcount
-    /// and can be converted into more `Arc<T, S>`s if necessary.
-    #[inline]
-    pub fn borrow_arc(&self) -> ArcBorrow<'_, T> {
-        ArcBorrow(&**self)
-    }
-
     /// Returns the address on the heap of the Arc itself -- not the T within it -- for memory
     /// reporting.
     pub fn heap_ptr(&self) -> *const c_void {
@@ -254,20 +403,23 @@ impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
     }
 
     #[inline]
-    pub(super) fn into_raw_inner(this: Self) -> *mut ArcInner<T> {
-        let this = ManuallyDrop::new(this);
-        this.ptr()
+    pub(super) fn into_raw_inner_with_allocator(this: Self) -> (*mut ArcInner<T>, A) {
+        let mut this = ManuallyDrop::new(this);
+        // Safety: `this` is never used (or dropped) again
+        let alloc = unsafe { ManuallyDrop::take(&mut this.alloc) };
+        (this.ptr(), alloc)
     }
 
-    /// Construct an `Arc` from an allocated `ArcInner`.
+    /// Construct an `Arc` from an `ArcInner` allocated in `alloc`.
     /// # Safety
-    /// The `ptr` must point to a valid instance, allocated by an `Arc`. The reference could will
+    /// The `ptr` must point to a valid instance, allocated by an `Arc` in `alloc`. The reference count will
     /// not be modified.
-    pub(super) unsafe fn from_raw_inner(ptr: *mut ArcInner<T>) -> Self {
+    pub(super) unsafe fn from_raw_inner_in(ptr: *mut ArcInner<T>, alloc: A) -> Self {
         Arc {
             p: ptr::NonNull::new_unchecked(ptr),
             phantom: PhantomData,
             phantom_strategy: PhantomData,
+            alloc: ManuallyDrop::new(alloc),
         }
     }
 
@@ -284,14 +436,15 @@ impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
     // Non-inlined part of `drop`. Just invokes the destructor.
     #[inline(never)]
     unsafe fn drop_slow(&mut self) {
-        let _ = Backdrop::<_, S>::new(Box::from_raw(self.ptr()));
+        let alloc = ManuallyDrop::take(&mut self.alloc);
+        let _ = Backdrop::<_, S>::new(Box::from_raw_in(self.ptr(), alloc));
     }
 
     /// Test pointer equality between the two Arcs, i.e. they must be the _same_
     /// allocation
     #[inline]
     pub fn ptr_eq(this: &Self, other: &Self) -> bool {
-        this.ptr() == other.ptr()
+        this.ptr() as *const () == other.ptr() as *const ()
     }
 
     pub(crate) fn ptr(&self) -> *mut ArcInner<T> {
@@ -314,6 +467,7 @@ impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
     #[allow(unused_unsafe)]
     pub(super) unsafe fn allocate_for_layout(
         value_layout: Layout,
+        alloc: &A,
         mem_to_arcinner: impl FnOnce(*mut u8) -> *mut ArcInner<T>,
     ) -> NonNull<ArcInner<T>> {
         let layout = Layout::new::<ArcInner<()>>()
@@ -324,7 +478,7 @@ impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
 
         // Safety: we propagate safety requirements to the caller
         unsafe {
-            Arc::<_, S>::try_allocate_for_layout(value_layout, mem_to_arcinner)
+            Arc::<_, S, A>::try_allocate_for_layout(value_layout, alloc, mem_to_arcinner)
                 .unwrap_or_else(|_| handle_alloc_error(layout))
         }
     }
@@ -344,17 +498,18 @@ impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
     ///
     /// `value_layout` must be correct for `T`.
     #[allow(unused_unsafe)]
-    unsafe fn try_allocate_for_layout(
+    pub(super) unsafe fn try_allocate_for_layout(
         value_layout: Layout,
+        alloc: &A,
         mem_to_arcinner: impl FnOnce(*mut u8) -> *mut ArcInner<T>,
-    ) -> Result<NonNull<ArcInner<T>>, ()> {
+    ) -> Result<NonNull<ArcInner<T>>, AllocError> {
         let layout = Layout::new::<ArcInner<()>>()
             .extend(value_layout)
-            .unwrap()
+            .map_err(|_| AllocError)?
             .0
             .pad_to_align();
 
-        let ptr = NonNull::new(alloc::alloc::alloc(layout)).ok_or(())?;
+        let ptr = alloc.allocate(layout).map_err(|_| AllocError)?.cast::<u8>();
 
         // Initialize the ArcInner
         let inner = mem_to_arcinner(ptr.as_ptr());
@@ -370,32 +525,73 @@ impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Arc<T, S> {
     }
 }
 
-impl<H, T, S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>> Arc<HeaderSlice<H, [T]>, S> {
+impl<H, T, S, A: Allocator> Arc<HeaderSlice<H, [T]>, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>, A>>,
+{
     pub(super) fn allocate_for_header_and_slice(
         len: usize,
+        alloc: &A,
     ) -> NonNull<ArcInner<HeaderSlice<H, [T]>>> {
-        let layout = Layout::new::<H>()
-            .extend(Layout::array::<T>(len).unwrap())
-            .unwrap()
-            .0
-            .pad_to_align();
+        let layout = Self::header_and_slice_layout(len).expect("capacity overflow");
 
         unsafe {
             // Safety:
             // - the provided closure does not change the pointer (except for meta & type)
             // - the provided layout is valid for `HeaderSlice<H, [T]>`
-            Arc::<_, S>::allocate_for_layout(layout, |mem| {
-                // Synthesize the fat pointer. We do this by claiming we have a direct
-                // pointer to a [T], and then changing the type of the borrow. The key
-                // point here is that the length portion of the fat pointer applies
-                // only to the number of elements in the dynamically-sized portion of
-                // the type, so the value will be the same whether it points to a [T]
-                // or something else with a [T] as its last member.
-                let fake_slice = ptr::slice_from_raw_parts_mut(mem as *mut T, len);
-                fake_slice as *mut ArcInner<HeaderSlice<H, [T]>>
+            Arc::<_, S, A>::allocate_for_layout(layout, alloc, |mem| {
+                Self::header_and_slice_ptr(mem, len)
             })
         }
     }
+
+    #[allow(clippy::type_complexity)]
+    pub(super) fn try_allocate_for_header_and_slice(
+        len: usize,
+        alloc: &A,
+    ) -> Result<NonNull<ArcInner<HeaderSlice<H, [T]>>>, AllocError> {
+        let layout = Self::header_and_slice_layout(len)?;
+
+        unsafe {
+            // Safety:
+            // - the provided closure does not change the pointer (except for meta & type)
+            // - the provided layout is valid for `HeaderSlice<H, [T]>`
+            Arc::<_, S, A>::try_allocate_for_layout(layout, alloc, |mem| {
+                Self::header_and_slice_ptr(mem, len)
+            })
+        }
+    }
+
+    /// The layout of the whole `ArcInner`, as allocated by `allocate_for_header_and_slice`.
+    pub(super) fn inner_layout_for_header_and_slice(len: usize) -> Result<Layout, AllocError> {
+        Ok(Layout::new::<ArcInner<()>>()
+            .extend(Self::header_and_slice_layout(len)?)
+            .map_err(|_| AllocError)?
+            .0
+            .pad_to_align())
+    }
+
+    pub(super) fn header_and_slice_layout(len: usize) -> Result<Layout, AllocError> {
+        Ok(Layout::new::<H>()
+            .extend(Layout::array::<T>(len).map_err(|_| AllocError)?)
+            .map_err(|_| AllocError)?
+            .0
+            .pad_to_align())
+    }
+
+    pub(super) fn header_and_slice_ptr(
+        mem: *mut u8,
+        len: usize,
+    ) -> *mut ArcInner<HeaderSlice<H, [T]>> {
+        // Synthesize the fat pointer. We do this by claiming we have a direct
+        // pointer to a [T], and then changing the type of the borrow. The key
+        // point here is that the length portion of the fat pointer applies
+        // only to the number of elements in the dynamically-sized portion of
+        // the type, so the value will be the same whether it points to a [T]
+        // or something else with a [T] as its last member.
+        let fake_slice = ptr::slice_from_raw_parts_mut(mem as *mut T, len);
+        fake_slice as *mut ArcInner<HeaderSlice<H, [T]>>
+    }
 }
 
 impl<T, S> Arc<MaybeUninit<T>, S>
@@ -408,6 +604,11 @@ where
         Arc::new(MaybeUninit::<T>::uninit())
     }
 
+    /// Create an Arc contains an `MaybeUninit<T>`, returning an error if allocation fails.
+    pub fn try_new_uninit() -> Result<Self, AllocError> {
+        Arc::try_new(MaybeUninit::<T>::uninit())
+    }
+
     /// Calls `MaybeUninit::write` on the value contained.
     ///
     /// ## Panics
@@ -447,6 +648,12 @@ where
         UniqueArc::new_uninit_slice(len).shareable()
     }
 
+    /// Create an Arc contains an array `[MaybeUninit<T>]` of `len`,
+    /// returning an error if allocation fails.
+    pub fn try_new_uninit_slice(len: usize) -> Result<Self, AllocError> {
+        UniqueArc::try_new_uninit_slice(len).map(UniqueArc::shareable)
+    }
+
     /// Obtain a mutable slice to the stored `[MaybeUninit<T>]`.
     #[deprecated(
         since = "0.1.8",
@@ -466,9 +673,9 @@ where
     }
 }
 
-impl<T: ?Sized, S> Clone for Arc<T, S>
+impl<T: ?Sized, S, A: Allocator + Clone> Clone for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     #[inline]
     fn clone(&self) -> Self {
@@ -498,13 +705,7 @@ where
             abort();
         }
 
-        unsafe {
-            Arc {
-                p: ptr::NonNull::new_unchecked(self.ptr()),
-                phantom: PhantomData,
-                phantom_strategy: PhantomData,
-            }
-        }
+        unsafe { Arc::from_raw_inner_in(self.ptr(), A::clone(&self.alloc)) }
     }
 }
 
@@ -517,15 +718,23 @@ where
 /// we decrease the refcount by the leftover amount _in one atomic operation_ to make sure the arc is not leaked.
 ///
 /// (if the iterator is empty, this step is of course skipped)
-#[derive(Debug, Hash, Clone)]
-pub struct ArcCloneIter<'a, T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> {
-    orig: &'a Arc<T, S>,
+#[derive(Debug, Hash)]
+pub struct ArcCloneIter<'a, T: ?Sized, S, A = Global>
+where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
+    A: Allocator + Clone,
+{
+    orig: &'a Arc<T, S, A>,
     arcs_left: usize,
 }
 
-impl<'a, T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> ArcCloneIter<'a, T, S> {
+impl<'a, T: ?Sized, S, A> ArcCloneIter<'a, T, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
+    A: Allocator + Clone,
+{
     #[inline]
-    fn new(orig: &'a Arc<T, S>, count: usize) -> Self {
+    fn new(orig: &'a Arc<T, S, A>, count: usize) -> Self {
         // Just like inside `clone`, we can use a Relaxed ordering:
         // Being passed `orig: &Arc<T, S>` ensures that for the duration of this function
         // (and the lifetime of the ArcCloneIter it returns),
@@ -550,7 +759,23 @@ impl<'a, T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> ArcCloneIter<'a, T, S
     }
 }
 
-impl<'a, T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Drop for ArcCloneIter<'a, T, S> {
+impl<'a, T: ?Sized, S, A> Clone for ArcCloneIter<'a, T, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
+    A: Allocator + Clone,
+{
+    /// The clone holds its own refcounts for the arcs it has left to give out.
+    #[inline]
+    fn clone(&self) -> Self {
+        ArcCloneIter::new(self.orig, self.arcs_left)
+    }
+}
+
+impl<'a, T: ?Sized, S, A> Drop for ArcCloneIter<'a, T, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
+    A: Allocator + Clone,
+{
     #[inline]
     fn drop(&mut self) {
         // If no arcs are left, no cleanup is necessary
@@ -569,8 +794,12 @@ impl<'a, T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Drop for ArcCloneIter
     }
 }
 
-impl<'a, T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Iterator for ArcCloneIter<'a, T, S> {
-    type Item = Arc<T, S>;
+impl<'a, T: ?Sized, S, A> Iterator for ArcCloneIter<'a, T, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
+    A: Allocator + Clone,
+{
+    type Item = Arc<T, S, A>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
@@ -580,13 +809,8 @@ impl<'a, T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Iterator for ArcClone
         self.arcs_left -= 1;
 
         // SAFETY: we only make a new arc when there still are refcounts left to give out
-        let new_arc = unsafe {
-            Arc {
-                p: ptr::NonNull::new_unchecked(self.orig.ptr()),
-                phantom: PhantomData,
-                phantom_strategy: PhantomData,
-            }
-        };
+        let new_arc =
+            unsafe { Arc::from_raw_inner_in(self.orig.ptr(), A::clone(&self.orig.alloc)) };
         Some(new_arc)
     }
 
@@ -596,14 +820,16 @@ impl<'a, T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Iterator for ArcClone
     }
 }
 
-impl<'a, T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> FusedIterator
-    for ArcCloneIter<'a, T, S>
+impl<'a, T: ?Sized, S, A> FusedIterator for ArcCloneIter<'a, T, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
+    A: Allocator + Clone,
 {
 }
 
-impl<T: ?Sized, S> Arc<T, S>
+impl<T: ?Sized, S, A: Allocator + Clone> Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     /// Optimization over calling `clone()` many times:
     ///
@@ -640,14 +866,50 @@ where
     /// let many_clones: Vec<_> = Arc::clone_many(&myarc, 1000).take(100).collect();
     /// assert_eq!(Arc::count(&myarc), 101);
     /// ```
-    pub fn clone_many<'a>(this: &'a Self, count: usize) -> ArcCloneIter<'a, T, S> {
+    pub fn clone_many<'a>(this: &'a Self, count: usize) -> ArcCloneIter<'a, T, S, A> {
         ArcCloneIter::new(this, count)
     }
 }
 
-impl<T: ?Sized, S> Deref for Arc<T, S>
+impl<S, A: Allocator> Arc<dyn Any + Send + Sync, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<dyn Any + Send + Sync>, A>>,
+{
+    /// Attempts to downcast the `Arc<dyn Any + Send + Sync, S>` to a concrete type.
+    ///
+    /// The refcount is not modified. On failure, the original `Arc` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backdrop_arc::{Arc, TrivialStrategy};
+    /// use std::any::Any;
+    ///
+    /// let any: Arc<dyn Any + Send + Sync, TrivialStrategy> = Arc::from(Box::new(42usize) as Box<dyn Any + Send + Sync>);
+    /// let any = any.downcast::<String>().unwrap_err();
+    /// let num: Arc<usize, TrivialStrategy> = any.downcast().unwrap();
+    /// assert_eq!(*num, 42);
+    /// ```
+    #[inline]
+    pub fn downcast<T>(self) -> Result<Arc<T, S, A>, Self>
+    where
+        T: Any + Send + Sync,
+        S: BackdropStrategy<Box<ArcInner<T>, A>>,
+    {
+        if (*self).is::<T>() {
+            let (ptr, alloc) = Arc::into_raw_inner_with_allocator(self);
+            // Safety: the value is a `T`, and `ArcInner` is `repr(C)`,
+            //         so the thin pointer to `ArcInner<T>` points to the same allocation
+            unsafe { Ok(Arc::from_raw_inner_in(ptr as *mut ArcInner<T>, alloc)) }
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<T: ?Sized, S, A: Allocator> Deref for Arc<T, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     type Target = T;
 
@@ -657,10 +919,67 @@ where
     }
 }
 
-impl<T: Clone, S> Arc<T, S>
+/// An [`Allocator`] that [`Arc::make_mut`] and [`Arc::make_unique`] can copy an `Arc` into.
+///
+/// Some allocators keep state per allocation, which a copy of the `Arc` should inherit:
+/// [`DynStrategyAlloc`](crate::DynStrategyAlloc) stores the drop strategy in front of each allocation.
+/// For allocators without such state, an empty implementation suffices:
+///
+/// ```
+/// # use backdrop_arc::allocator_api2::alloc::{AllocError, Allocator, Global};
+/// # use std::alloc::Layout;
+/// # use std::ptr::NonNull;
+/// use backdrop_arc::CopyOnWriteAllocator;
+///
+/// #[derive(Clone)]
+/// struct MyAlloc;
+/// # unsafe impl Allocator for MyAlloc {
+/// #     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> { Global.allocate(layout) }
+/// #     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) { Global.deallocate(ptr, layout) }
+/// # }
+///
+/// impl CopyOnWriteAllocator for MyAlloc {}
+/// ```
+pub trait CopyOnWriteAllocator: Allocator + Clone {
+    /// Called after the contents of the allocation at `from` were cloned into the new allocation at `to`,
+    /// both made by this allocator with `layout`.
+    ///
+    /// # Safety
+    ///
+    /// `from` and `to` must be live allocations made by this allocator with `layout`.
+    #[inline]
+    unsafe fn copy_allocation_state(&self, from: NonNull<u8>, to: NonNull<u8>, layout: Layout) {
+        let _ = (from, to, layout);
+    }
+}
+
+impl CopyOnWriteAllocator for Global {}
+
+impl<A: CopyOnWriteAllocator> CopyOnWriteAllocator for &A {
+    #[inline]
+    unsafe fn copy_allocation_state(&self, from: NonNull<u8>, to: NonNull<u8>, layout: Layout) {
+        (**self).copy_allocation_state(from, to, layout)
+    }
+}
+
+impl<T: Clone, S, A: CopyOnWriteAllocator> Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
+    /// Copies the contents into a new allocation in the same allocator.
+    fn clone_into_new(this: &Self) -> Self {
+        let copy = Arc::new_in(T::clone(this), A::clone(&this.alloc));
+        // Safety: both were allocated in (a clone of) this allocator, with the layout of an `ArcInner<T>`
+        unsafe {
+            this.alloc.copy_allocation_state(
+                NonNull::new_unchecked(this.ptr()).cast(),
+                NonNull::new_unchecked(copy.ptr()).cast(),
+                Layout::new::<ArcInner<T>>(),
+            );
+        }
+        copy
+    }
+
     /// Makes a mutable reference to the `Arc`, cloning if necessary
     ///
     /// This is functionally equivalent to [`Arc::make_mut`][mm] from the standard library.
@@ -678,7 +997,7 @@ where
     pub fn make_mut(this: &mut Self) -> &mut T {
         if !this.is_unique() {
             // Another pointer exists; clone
-            *this = Arc::new(T::clone(&this));
+            *this = Arc::clone_into_new(this);
         }
 
         unsafe {
@@ -701,10 +1020,10 @@ where
     /// This is useful for implementing copy-on-write schemes where you wish to
     /// avoid copying things if your `Arc` is not shared.
     #[inline]
-    pub fn make_unique(this: &mut Self) -> &mut UniqueArc<T, S> {
+    pub fn make_unique(this: &mut Self) -> &mut UniqueArc<T, S, A> {
         if !this.is_unique() {
             // Another pointer exists; clone
-            *this = Arc::new(T::clone(&this));
+            *this = Arc::clone_into_new(this);
         }
 
         unsafe {
@@ -716,14 +1035,14 @@ where
     /// If we have the only reference to `T` then unwrap it. Otherwise, clone `T` and return the clone.
     ///
     /// Assuming `arc_t` is of type `Arc<T, S>`, this function is functionally equivalent to `(*arc_t).clone()`, but will avoid cloning the inner value where possible.
-    pub fn unwrap_or_clone(this: Arc<T, S>) -> T {
+    pub fn unwrap_or_clone(this: Arc<T, S, A>) -> T {
         Self::try_unwrap(this).unwrap_or_else(|this| T::clone(&this))
     }
 }
 
-impl<T: ?Sized, S> Arc<T, S>
+impl<T: ?Sized, S, A: Allocator> Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     /// Provides mutable access to the contents _if_ the `Arc` is uniquely owned.
     #[inline]
@@ -739,7 +1058,7 @@ where
     }
 
     /// Provides unique access to the arc _if_ the `Arc` is uniquely owned.
-    pub fn get_unique(this: &mut Self) -> Option<&mut UniqueArc<T, S>> {
+    pub fn get_unique(this: &mut Self) -> Option<&mut UniqueArc<T, S, A>> {
         Self::try_as_unique(this).ok()
     }
 
@@ -776,7 +1095,7 @@ where
     ///     4,
     /// );
     /// ```
-    pub fn try_unique(this: Self) -> Result<UniqueArc<T, S>, Self> {
+    pub fn try_unique(this: Self) -> Result<UniqueArc<T, S, A>, Self> {
         if this.is_unique() {
             // Safety: The current arc is unique and making a `UniqueArc`
             //         from it is sound
@@ -786,7 +1105,7 @@ where
         }
     }
 
-    pub(crate) fn try_as_unique(this: &mut Self) -> Result<&mut UniqueArc<T, S>, &mut Self> {
+    pub(crate) fn try_as_unique(this: &mut Self) -> Result<&mut UniqueArc<T, S, A>, &mut Self> {
         if this.is_unique() {
             // Safety: The current arc is unique and making a `UniqueArc`
             //         from it is sound
@@ -797,15 +1116,17 @@ where
     }
 }
 
-impl<T: ?Sized, S> Drop for Arc<T, S>
+impl<T: ?Sized, S, A: Allocator> Drop for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     #[inline]
     fn drop(&mut self) {
         // Because `fetch_sub` is already atomic, we do not need to synchronize
         // with other threads unless we are going to delete the object.
         if self.inner().count.fetch_sub(1, Release) != 1 {
+            // Safety: `self.alloc` is never used again
+            unsafe { ManuallyDrop::drop(&mut self.alloc) };
             return;
         }
 
@@ -837,77 +1158,80 @@ where
     }
 }
 
-impl<T: ?Sized + PartialEq, S> PartialEq for Arc<T, S>
+impl<T: ?Sized + PartialEq, S, A: Allocator> PartialEq for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
-    fn eq(&self, other: &Arc<T, S>) -> bool {
+    fn eq(&self, other: &Arc<T, S, A>) -> bool {
         Self::ptr_eq(self, other) || *(*self) == *(*other)
     }
 
     #[allow(clippy::partialeq_ne_impl)]
-    fn ne(&self, other: &Arc<T, S>) -> bool {
+    fn ne(&self, other: &Arc<T, S, A>) -> bool {
         !Self::ptr_eq(self, other) && *(*self) != *(*other)
     }
 }
 
-impl<T: ?Sized + PartialOrd, S> PartialOrd for Arc<T, S>
+impl<T: ?Sized + PartialOrd, S, A: Allocator> PartialOrd for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
-    fn partial_cmp(&self, other: &Arc<T, S>) -> Option<Ordering> {
+    fn partial_cmp(&self, other: &Arc<T, S, A>) -> Option<Ordering> {
         (**self).partial_cmp(&**other)
     }
 
-    fn lt(&self, other: &Arc<T, S>) -> bool {
+    fn lt(&self, other: &Arc<T, S, A>) -> bool {
         *(*self) < *(*other)
     }
 
-    fn le(&self, other: &Arc<T, S>) -> bool {
+    fn le(&self, other: &Arc<T, S, A>) -> bool {
         *(*self) <= *(*other)
     }
 
-    fn gt(&self, other: &Arc<T, S>) -> bool {
+    fn gt(&self, other: &Arc<T, S, A>) -> bool {
         *(*self) > *(*other)
     }
 
-    fn ge(&self, other: &Arc<T, S>) -> bool {
+    fn ge(&self, other: &Arc<T, S, A>) -> bool {
         *(*self) >= *(*other)
     }
 }
 
-impl<T: ?Sized + Ord, S> Ord for Arc<T, S>
+impl<T: ?Sized + Ord, S, A: Allocator> Ord for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
-    fn cmp(&self, other: &Arc<T, S>) -> Ordering {
+    fn cmp(&self, other: &Arc<T, S, A>) -> Ordering {
         (**self).cmp(&**other)
     }
 }
 
-impl<T: ?Sized + Eq, S> Eq for Arc<T, S> where S: BackdropStrategy<Box<ArcInner<T>>> {}
+impl<T: ?Sized + Eq, S, A: Allocator> Eq for Arc<T, S, A> where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>
+{
+}
 
-impl<T: ?Sized + fmt::Display, S> fmt::Display for Arc<T, S>
+impl<T: ?Sized + fmt::Display, S, A: Allocator> fmt::Display for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Display::fmt(&**self, f)
     }
 }
 
-impl<T: ?Sized + fmt::Debug, S> fmt::Debug for Arc<T, S>
+impl<T: ?Sized + fmt::Debug, S, A: Allocator> fmt::Debug for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Debug::fmt(&**self, f)
     }
 }
 
-impl<T: ?Sized, S> fmt::Pointer for Arc<T, S>
+impl<T: ?Sized, S, A: Allocator> fmt::Pointer for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Pointer::fmt(&self.ptr(), f)
@@ -924,9 +1248,9 @@ where
     }
 }
 
-impl<T: ?Sized + Hash, S> Hash for Arc<T, S>
+impl<T: ?Sized + Hash, S, A: Allocator> Hash for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     fn hash<H: Hasher>(&self, state: &mut H) {
         (**self).hash(state)
@@ -944,20 +1268,25 @@ impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> From<T> for Arc<T, S> {
 extern crate triomphe;
 
 #[cfg(feature = "triomphe")]
-/// Converting to- and from a [`triomphe::Arc<T>`] is a zero-cost operation
+/// Converting to- and from a [`triomphe::Arc<T>`] does not allocate or copy,
+/// since both use the same `ArcInner` layout.
 impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> From<triomphe::Arc<T>> for Arc<T, S> {
     #[inline]
     fn from(arc: triomphe::Arc<T>) -> Self {
-        unsafe { core::mem::transmute(arc) }
+        // Safety: `triomphe::ArcInner<T>` is laid out like `ArcInner<T>`,
+        // so the data pointer is at the same offset from the refcount.
+        unsafe { Arc::from_raw(triomphe::Arc::into_raw(arc)) }
     }
 }
 
 #[cfg(feature = "triomphe")]
-/// Converting to- and from a [`triomphe::Arc<T>`] is a zero-cost operation
+/// Converting to- and from a [`triomphe::Arc<T>`] does not allocate or copy,
+/// since both use the same `ArcInner` layout.
 impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> From<Arc<T, S>> for triomphe::Arc<T> {
     #[inline]
     fn from(arc: Arc<T, S>) -> Self {
-        unsafe { core::mem::transmute(arc) }
+        // Safety: see above
+        unsafe { triomphe::Arc::from_raw(Arc::into_raw(arc)) }
     }
 }
 
@@ -971,30 +1300,36 @@ where
     }
 }
 
-impl<T: ?Sized, S> borrow::Borrow<T> for Arc<T, S>
+impl<T: ?Sized, S, A: Allocator> borrow::Borrow<T> for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     #[inline]
     fn borrow(&self) -> &T {
-        &**self
+        self
     }
 }
 
-impl<T: ?Sized, S> AsRef<T> for Arc<T, S>
+impl<T: ?Sized, S, A: Allocator> AsRef<T> for Arc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     #[inline]
     fn as_ref(&self) -> &T {
-        &**self
+        self
     }
 }
 
 #[cfg(feature = "stable_deref_trait")]
-unsafe impl<T: ?Sized, S> StableDeref for Arc<T, S> where S: BackdropStrategy<Box<ArcInner<T>>> {}
+unsafe impl<T: ?Sized, S, A: Allocator> StableDeref for Arc<T, S, A> where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>
+{
+}
 #[cfg(feature = "stable_deref_trait")]
-unsafe impl<T: ?Sized, S> CloneStableDeref for Arc<T, S> where S: BackdropStrategy<Box<ArcInner<T>>> {}
+unsafe impl<T: ?Sized, S, A: Allocator + Clone> CloneStableDeref for Arc<T, S, A> where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>
+{
+}
 
 #[cfg(feature = "serde")]
 impl<'de, T: Deserialize<'de>, S> Deserialize<'de> for Arc<T, S>
@@ -1010,7 +1345,7 @@ where
 }
 
 #[cfg(feature = "serde")]
-impl<T: Serialize, S> Serialize for Arc<T, S>
+impl<T: ?Sized + Serialize, S> Serialize for Arc<T, S>
 where
     S: BackdropStrategy<Box<ArcInner<T>>>,
 {
@@ -1075,13 +1410,19 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::backdrop::TrivialStrategy;
-    use crate::arc::Arc;
+    use super::backdrop::{LeakStrategy, TrivialStrategy};
+    use crate::arc::{AllocError, Arc};
+    use alloc::alloc::Layout;
     use alloc::borrow::ToOwned;
+    use alloc::boxed::Box;
     use alloc::string::String;
     use alloc::vec::Vec;
+    use allocator_api2::alloc::{Allocator, Global};
+    use core::any::Any;
     use core::iter::FromIterator;
     use core::mem::MaybeUninit;
+    use core::ptr::NonNull;
+    use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
     #[cfg(feature = "unsize")]
     use unsize::{CoerceUnsize, Coercion};
 
@@ -1100,6 +1441,103 @@ mod tests {
         assert_eq!(Arc::try_unwrap(y), Ok(100));
     }
 
+    #[test]
+    fn downcast() {
+        let x: Arc<dyn Any + Send + Sync, TrivialStrategy> =
+            Arc::from(Box::new(String::from("any")) as Box<dyn Any + Send + Sync>);
+        let y = x.clone();
+
+        let x = x.downcast::<usize>().unwrap_err();
+        let x: Arc<String, TrivialStrategy> = x.downcast().unwrap();
+        assert_eq!(*x, "any");
+        assert_eq!(Arc::count(&x), 2);
+
+        let borrowed = y.borrow_arc();
+        assert!(borrowed.downcast_ref::<usize>().is_none());
+        let z: Arc<String, TrivialStrategy> =
+            borrowed.downcast_ref::<String>().unwrap().clone_arc();
+        assert!(Arc::ptr_eq(&x, &z));
+        assert_eq!(Arc::count(&x), 3);
+    }
+
+    #[test]
+    fn try_new() {
+        let x = Arc::<_, TrivialStrategy>::try_new(String::from("fallible")).unwrap();
+        assert_eq!(*x, "fallible");
+        assert_eq!(Arc::count(&x), 1);
+    }
+
+    #[test]
+    fn try_new_uninit_slice_overflow() {
+        let res = Arc::<[MaybeUninit<u32>], TrivialStrategy>::try_new_uninit_slice(usize::MAX / 2);
+        assert_eq!(res.unwrap_err(), AllocError);
+
+        let arc = Arc::<[MaybeUninit<u32>], TrivialStrategy>::try_new_uninit_slice(3).unwrap();
+        assert_eq!(arc.len(), 3);
+    }
+
+    #[derive(Clone, Copy)]
+    struct CountingAlloc<'a>(&'a AtomicUsize);
+
+    unsafe impl<'a> Allocator for CountingAlloc<'a> {
+        fn allocate(
+            &self,
+            layout: Layout,
+        ) -> Result<NonNull<[u8]>, allocator_api2::alloc::AllocError> {
+            self.0.fetch_add(1, Relaxed);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.0.fetch_sub(1, Relaxed);
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn new_in_custom_allocator() {
+        let live = AtomicUsize::new(0);
+        let x = Arc::<_, TrivialStrategy, _>::new_in(String::from("arena"), CountingAlloc(&live));
+        assert_eq!(live.load(Relaxed), 1);
+
+        let y = x.clone();
+        assert_eq!(*y, "arena");
+        drop(x);
+        assert_eq!(live.load(Relaxed), 1);
+        drop(y);
+        assert_eq!(live.load(Relaxed), 0);
+
+        let z = Arc::<_, TrivialStrategy, _>::try_new_in(42usize, CountingAlloc(&live)).unwrap();
+        assert_eq!(Arc::try_unwrap(z), Ok(42));
+        assert_eq!(live.load(Relaxed), 0);
+    }
+
+    #[test]
+    fn custom_allocator_with_strategy() {
+        let live = AtomicUsize::new(0);
+        let x = Arc::<_, TrivialStrategy, _>::new_in(Vec::from([1, 2, 3]), CountingAlloc(&live));
+        let x: Arc<_, LeakStrategy, _> = Arc::with_strategy(x);
+        drop(x);
+        // The strategy received the box (including its allocator) and leaked it
+        assert_eq!(live.load(Relaxed), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "triomphe")]
+    fn triomphe_roundtrip() {
+        use super::triomphe;
+
+        let x: Arc<String, TrivialStrategy> = Arc::new(String::from("shared"));
+        let ptr: *const String = &*x;
+        let y = triomphe::Arc::from(x.clone());
+        assert_eq!(triomphe::Arc::count(&y), 2);
+        let z: Arc<String, TrivialStrategy> = Arc::from(y);
+        assert_eq!(&*z as *const String, ptr);
+        assert!(Arc::ptr_eq(&x, &z));
+        drop(z);
+        assert!(Arc::is_unique(&x));
+    }
+
     #[test]
     #[cfg(feature = "unsize")]
     fn coerce_to_slice() {
@@ -1116,6 +1554,20 @@ mod tests {
         assert_eq!((*x)(), 42);
     }
 
+    #[test]
+    fn clone_many_cloned_iter() {
+        let arc = Arc::<_, TrivialStrategy>::new(1u8);
+        let mut iter = Arc::clone_many(&arc, 5);
+        let first = iter.next().unwrap();
+        let cloned = iter.clone();
+        assert_eq!(Arc::count(&arc), 10);
+        drop(iter);
+        drop(cloned);
+        assert_eq!(Arc::count(&arc), 2);
+        drop(first);
+        assert!(Arc::is_unique(&arc));
+    }
+
     #[test]
     #[allow(deprecated)]
     fn maybeuninit() {
@@ -1133,12 +1585,13 @@ mod tests {
         let mut uninit = Arc::<_, TrivialStrategy>::new_uninit();
         let clone = uninit.clone();
 
-        let x: &MaybeUninit<String> = &*clone;
+        let x: &MaybeUninit<String> = &clone;
 
         // This write invalidates `x` reference
         uninit.write(String::from("nonononono"));
 
         // Read invalidated reference to trigger UB
+        #[allow(clippy::borrow_deref_ref)]
         let _ = &*x;
     }
 
@@ -1149,12 +1602,13 @@ mod tests {
         let mut uninit = Arc::<_, TrivialStrategy>::new_uninit_slice(13);
         let clone = uninit.clone();
 
-        let x: &[MaybeUninit<String>] = &*clone;
+        let x: &[MaybeUninit<String>] = &clone;
 
         // This write invalidates `x` reference
         uninit.as_mut_slice()[0].write(String::from("nonononono"));
 
         // Read invalidated reference to trigger UB
+        #[allow(clippy::borrow_deref_ref)]
         let _ = &*x;
     }
 
@@ -1,9 +1,15 @@
-use alloc::boxed::Box;
+use alloc::alloc::Layout;
+use allocator_api2::boxed::Box;
+use core::any::Any;
+use core::fmt;
+use core::marker::PhantomData;
 use core::mem;
 use core::mem::ManuallyDrop;
 use core::ops::Deref;
 use core::ptr;
+use core::sync::atomic::AtomicUsize;
 extern crate backdrop;
+use crate::header::set_data_ptr;
 use crate::ArcInner;
 
 use self::backdrop::BackdropStrategy;
@@ -25,45 +31,91 @@ use super::Arc;
 ///
 /// `ArcBorrow` lets us deal with borrows of known-refcounted objects
 /// without needing to worry about where the `Arc<T, S>` is.
-#[derive(Debug, Eq, PartialEq)]
+///
+/// `T` may be unsized, e.g. `[T]`, `str` or a `HeaderSlice`.
+///
+/// By default, an `ArcBorrow` does not know the strategy of the `Arc` it borrows from,
+/// so it has to be restated when converting it back into an `Arc` with `clone_arc::<S>()`.
+/// An `ArcBorrow<'a, T, S>` (obtained using [`Arc::borrow_arc_with_strategy`])
+/// remembers it instead, so `clone_arc()` can only produce the original `Arc<T, S>`:
+///
+/// ```
+/// use backdrop_arc::{Arc, ArcBorrow, TrivialStrategy};
+///
+/// let arc: Arc<str, TrivialStrategy> = Arc::from("hello");
+/// let borrow: ArcBorrow<'_, str, TrivialStrategy> = arc.borrow_arc_with_strategy();
+/// let clone = borrow.clone_arc(); // an `Arc<str, TrivialStrategy>`
+/// assert!(Arc::ptr_eq(&arc, &clone));
+/// ```
 #[repr(transparent)]
-pub struct ArcBorrow<'a, T: ?Sized + 'a>(pub(crate) &'a T);
+pub struct ArcBorrow<'a, T: ?Sized + 'a, S = UnknownStrategy>(
+    pub(crate) &'a T,
+    pub(crate) PhantomData<fn() -> S>,
+);
+
+/// The strategy of an [`ArcBorrow`] which does not know the strategy of the `Arc` it borrows from.
+///
+/// This type cannot be constructed, and is not a `BackdropStrategy` itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnknownStrategy {}
 
-impl<'a, T> Copy for ArcBorrow<'a, T> {}
-impl<'a, T> Clone for ArcBorrow<'a, T> {
+impl<'a, T: ?Sized, S> Copy for ArcBorrow<'a, T, S> {}
+impl<'a, T: ?Sized, S> Clone for ArcBorrow<'a, T, S> {
     #[inline]
     fn clone(&self) -> Self {
         *self
     }
 }
 
-impl<'a, T> ArcBorrow<'a, T> {
+impl<'a, T: ?Sized + fmt::Debug, S> fmt::Debug for ArcBorrow<'a, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ArcBorrow").field(&self.0).finish()
+    }
+}
+
+impl<'a, T: ?Sized + PartialEq, S> PartialEq for ArcBorrow<'a, T, S> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        *self.0 == *other.0
+    }
+}
+
+impl<'a, T: ?Sized + Eq, S> Eq for ArcBorrow<'a, T, S> {}
+
+/// Finds the (possibly fat) pointer to the `ArcInner` whose data `data` is.
+///
+/// # Safety
+///
+/// `data` must point to the data of a live `ArcInner<T>`.
+#[inline]
+unsafe fn inner_ptr<T: ?Sized>(data: &T) -> *mut ArcInner<T> {
+    // `ArcInner` is `repr(C)`, so the data follows the count, padded to its own alignment.
+    let (_, offset) = Layout::new::<AtomicUsize>()
+        .extend(Layout::for_value(data))
+        .expect("data was allocated in an ArcInner");
+    let data = data as *const T as *mut T;
+    set_data_ptr(data as *mut ArcInner<T>, (data as *mut u8).sub(offset))
+}
+
+impl<'a, T: ?Sized> ArcBorrow<'a, T> {
     /// Clone this as an `Arc<T, S>`. This bumps the refcount.
     #[inline]
     pub fn clone_arc<S>(&self) -> Arc<T, S>
     where
         S: BackdropStrategy<Box<ArcInner<T>>>,
     {
-        let arc = unsafe { Arc::from_raw(self.0) };
-        // addref it!
-        mem::forget(arc.clone());
-        arc
+        unsafe { ArcBorrow::with_strategy::<S>(*self) }.clone_arc()
     }
 
     /// For constructing from a reference known to be Arc-backed,
     /// e.g. if we obtain such a reference over FFI
-    /// TODO: should from_ref be relaxed to unsized types? It can't be
-    /// converted back to an Arc right now for unsized types.
+    ///
+    /// # Safety
+    ///
+    /// `r` must point to the data of a live `ArcInner<T>`.
     #[inline]
     pub unsafe fn from_ref(r: &'a T) -> Self {
-        ArcBorrow(r)
-    }
-
-    /// Compare two `ArcBorrow`s via pointer equality. Will only return
-    /// true if they come from the same allocation
-    #[inline]
-    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
-        ptr::eq(this.0 as *const T, other.0 as *const T)
+        ArcBorrow(r, PhantomData)
     }
 
     /// Temporarily converts |self| into a bonafide Arc and exposes it to the
@@ -74,15 +126,67 @@ impl<'a, T> ArcBorrow<'a, T> {
         F: FnOnce(&Arc<T, S>) -> U,
         T: 'static,
         S: BackdropStrategy<Box<ArcInner<T>>>,
+    {
+        unsafe { ArcBorrow::with_strategy::<S>(*self) }.with_arc(f)
+    }
+
+    /// Remembers the strategy of the `Arc` this borrows from.
+    ///
+    /// # Safety
+    ///
+    /// The `Arc` this borrows from must be an `Arc<T, S>`.
+    #[inline]
+    pub unsafe fn with_strategy<S>(this: ArcBorrow<'a, T>) -> ArcBorrow<'a, T, S>
+    where
+        S: BackdropStrategy<Box<ArcInner<T>>>,
+    {
+        ArcBorrow(this.0, PhantomData)
+    }
+}
+
+impl<'a, T: ?Sized, S> ArcBorrow<'a, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    /// Clone this as the `Arc<T, S>` it borrows from. This bumps the refcount.
+    #[inline]
+    pub fn clone_arc(&self) -> Arc<T, S> {
+        let arc = unsafe { Arc::from_raw_inner(inner_ptr(self.0)) };
+        // addref it!
+        mem::forget(arc.clone());
+        arc
+    }
+
+    /// Temporarily converts |self| into the `Arc<T, S>` it borrows from
+    /// and exposes it to the provided callback. The refcount is not modified.
+    #[inline]
+    pub fn with_arc<F, U>(&self, f: F) -> U
+    where
+        F: FnOnce(&Arc<T, S>) -> U,
     {
         // Synthesize transient Arc, which never touches the refcount.
-        let transient = unsafe { ManuallyDrop::new(Arc::from_raw(self.0)) };
+        let transient = unsafe { ManuallyDrop::new(Arc::from_raw_inner(inner_ptr(self.0))) };
 
         // Expose the transient Arc to the callback, which may clone it if it wants
         // and forward the result to the user
         f(&transient)
     }
 
+    /// Forgets the strategy of the `Arc` this borrows from.
+    #[inline]
+    pub fn without_strategy(this: Self) -> ArcBorrow<'a, T> {
+        ArcBorrow(this.0, PhantomData)
+    }
+}
+
+impl<'a, T: ?Sized, S> ArcBorrow<'a, T, S> {
+    /// Compare two `ArcBorrow`s via pointer equality. Will only return
+    /// true if they come from the same allocation
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        ptr::eq(this.0 as *const T, other.0 as *const T)
+    }
+
     /// Similar to deref, but uses the lifetime |a| rather than the lifetime of
     /// self, which is incompatible with the signature of the Deref trait.
     #[inline]
@@ -91,7 +195,30 @@ impl<'a, T> ArcBorrow<'a, T> {
     }
 }
 
-impl<'a, T> Deref for ArcBorrow<'a, T> {
+impl<'a, T: ?Sized, S> From<&'a Arc<T, S>> for ArcBorrow<'a, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    #[inline]
+    fn from(arc: &'a Arc<T, S>) -> Self {
+        arc.borrow_arc_with_strategy()
+    }
+}
+
+impl<'a> ArcBorrow<'a, dyn Any + Send + Sync> {
+    /// Attempts to downcast the borrow to a concrete type.
+    ///
+    /// The resulting `ArcBorrow<'a, T>` can be cloned into an `Arc<T, S>` as usual.
+    #[inline]
+    pub fn downcast_ref<T: Any + Send + Sync>(&self) -> Option<ArcBorrow<'a, T>> {
+        // The downcast reference still points to the data of the same `ArcInner`
+        self.0
+            .downcast_ref::<T>()
+            .map(|data| ArcBorrow(data, PhantomData))
+    }
+}
+
+impl<'a, T: ?Sized, S> Deref for ArcBorrow<'a, T, S> {
     type Target = T;
 
     #[inline]
@@ -120,6 +247,58 @@ unsafe impl<'lt, T: 'lt, U: ?Sized + 'lt> unsize::CoerciblePtr<U> for ArcBorrow<
     unsafe fn replace_ptr(self, new: *mut U) -> ArcBorrow<'lt, U> {
         let inner = ManuallyDrop::new(self);
         // Safety: backed by the same Arc that backed `self`.
-        ArcBorrow(inner.0.replace_ptr(new))
+        ArcBorrow(inner.0.replace_ptr(new), PhantomData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::TrivialStrategy;
+    use super::ArcBorrow;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use crate::{Arc, HeaderSlice};
+
+    #[test]
+    fn unsized_borrows() {
+        let slice: Arc<[String], TrivialStrategy> = (0..3)
+            .map(|_| String::from("elem"))
+            .collect::<Vec<_>>()
+            .into();
+        let borrow = slice.borrow_arc();
+        let copy = borrow;
+        let clone = copy.clone_arc::<TrivialStrategy>();
+        assert!(Arc::ptr_eq(&slice, &clone));
+        assert_eq!(Arc::count(&slice), 2);
+        assert_eq!(borrow.len(), 3);
+
+        let string: Arc<str, TrivialStrategy> = Arc::from("hello");
+        let count = string
+            .borrow_arc()
+            .with_arc(Arc::<str, TrivialStrategy>::count);
+        assert_eq!(count, 1);
+
+        #[repr(align(32))]
+        struct Aligned(u8);
+        let header: Arc<HeaderSlice<Aligned, [u16]>, TrivialStrategy> =
+            Arc::from_header_and_slice(Aligned(1), &[1, 2, 3]);
+        let clone = header.borrow_arc().clone_arc::<TrivialStrategy>();
+        assert!(Arc::ptr_eq(&header, &clone));
+        assert_eq!(clone.header.0, 1);
+        assert_eq!(clone.slice, [1, 2, 3]);
+    }
+
+    #[test]
+    fn borrow_with_strategy() {
+        let arc: Arc<[u8], TrivialStrategy> = Arc::from(&[1, 2, 3][..]);
+        let borrow: ArcBorrow<'_, [u8], TrivialStrategy> = ArcBorrow::from(&arc);
+        let clone = borrow.clone_arc();
+        assert!(Arc::ptr_eq(&arc, &clone));
+        assert_eq!(borrow.with_arc(Arc::count), 2);
+
+        let untagged = ArcBorrow::without_strategy(borrow);
+        assert!(ArcBorrow::ptr_eq(&untagged, &arc.borrow_arc()));
+        assert_eq!(*untagged, [1, 2, 3]);
     }
 }
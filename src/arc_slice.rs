@@ -0,0 +1,283 @@
+use allocator_api2::boxed::Box;
+use core::borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Bound, Deref, RangeBounds};
+
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+use super::{Arc, ArcInner};
+
+/// A view into a sub-slice of an `Arc<[T], S>`.
+///
+/// Slicing and splitting an `ArcSlice` does not copy any elements:
+/// all views into the same `Arc<[T], S>` share its refcount,
+/// and the backdrop strategy `S` is executed once the last view (or the `Arc` itself) goes out of scope.
+///
+/// Equality, ordering and hashing only consider the elements in view.
+///
+/// ```
+/// use backdrop_arc::{Arc, ArcSlice, TrivialStrategy};
+///
+/// let buffer: Arc<[u8], TrivialStrategy> = Arc::from(&b"GET /index.html"[..]);
+/// let mut request = ArcSlice::from(buffer.clone());
+///
+/// let path = request.split_off(4);
+/// assert_eq!(&*request, b"GET ");
+/// assert_eq!(&*path, b"/index.html");
+/// assert_eq!(&*path.slice(1..6), b"index");
+/// assert_eq!(Arc::count(&buffer), 3);
+/// ```
+pub struct ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    arc: Arc<[T], S>,
+    // Invariant: `start + len <= arc.len()`
+    start: usize,
+    len: usize,
+}
+
+impl<T, S> ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    /// Creates a view of the whole `Arc`.
+    #[inline]
+    pub fn new(arc: Arc<[T], S>) -> Self {
+        let len = arc.len();
+        ArcSlice { arc, start: 0, len }
+    }
+
+    /// Returns a view of a sub-slice of this view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds, like indexing a slice does.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> Self {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1).expect("range start overflows"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1).expect("range end overflows"),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len,
+        };
+        assert!(
+            start <= end,
+            "range start {} is greater than range end {}",
+            start,
+            end
+        );
+        assert!(
+            end <= self.len,
+            "range end {} is out of range for a slice of length {}",
+            end,
+            self.len
+        );
+
+        ArcSlice {
+            arc: self.arc.clone(),
+            start: self.start + start,
+            len: end - start,
+        }
+    }
+
+    /// Splits the view into two at an index, like `<[T]>::split_at`.
+    ///
+    /// The first view contains the elements `[0, mid)`, the second one `[mid, len)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > len`.
+    pub fn split_at(&self, mid: usize) -> (Self, Self) {
+        (self.slice(..mid), self.slice(mid..))
+    }
+
+    /// Splits the view into two at an index, like `Vec::split_off`.
+    ///
+    /// Afterwards, `self` contains the elements `[0, at)`,
+    /// and the returned view contains the elements `[at, len)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let tail = self.slice(at..);
+        self.len = at;
+        tail
+    }
+
+    /// The `Arc` this is a view into.
+    #[inline]
+    pub fn parent(this: &Self) -> &Arc<[T], S> {
+        &this.arc
+    }
+
+    /// Whether the two views are into the same `Arc`, regardless of which elements they view.
+    #[inline]
+    pub fn parent_ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.arc, &other.arc)
+    }
+}
+
+impl<T, S> Clone for ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        ArcSlice {
+            arc: self.arc.clone(),
+            start: self.start,
+            len: self.len,
+        }
+    }
+}
+
+impl<T, S> Deref for ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &[T] {
+        // Safety: `start + len <= arc.len()`
+        unsafe { self.arc.get_unchecked(self.start..self.start + self.len) }
+    }
+}
+
+impl<T, S> From<Arc<[T], S>> for ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    #[inline]
+    fn from(arc: Arc<[T], S>) -> Self {
+        ArcSlice::new(arc)
+    }
+}
+
+impl<T: PartialEq, S> PartialEq for ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    fn eq(&self, other: &ArcSlice<T, S>) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Eq, S> Eq for ArcSlice<T, S> where S: BackdropStrategy<Box<ArcInner<[T]>>> {}
+
+impl<T: PartialOrd, S> PartialOrd for ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    fn partial_cmp(&self, other: &ArcSlice<T, S>) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: Ord, S> Ord for ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    fn cmp(&self, other: &ArcSlice<T, S>) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: Hash, S> Hash for ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T, S> borrow::Borrow<[T]> for ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    #[inline]
+    fn borrow(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, S> AsRef<[T]> for ArcSlice<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    #[inline]
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::TrivialStrategy;
+    use super::ArcSlice;
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    use crate::Arc;
+
+    #[test]
+    fn views_share_the_arc() {
+        let arc: Arc<[String], TrivialStrategy> = (0..10).map(|i| i.to_string()).collect();
+        let view = ArcSlice::from(arc.clone());
+        assert_eq!(view.len(), 10);
+
+        let middle = view.slice(2..=5);
+        assert_eq!(&*middle, ["2", "3", "4", "5"]);
+        let (left, right) = middle.split_at(1);
+        assert_eq!(&*left, ["2"]);
+        assert_eq!(&*right, ["3", "4", "5"]);
+        assert_eq!(&*right.slice(1..), ["4", "5"]);
+        assert!(right.slice(3..).is_empty());
+        assert!(ArcSlice::parent_ptr_eq(&left, &view));
+
+        let mut head = view.clone();
+        let tail = head.split_off(8);
+        assert_eq!(&*head, &arc[..8]);
+        assert_eq!(&*tail, ["8", "9"]);
+        assert_eq!(Arc::count(&arc), 7);
+
+        drop((view, middle, left, right, head, tail));
+        assert!(Arc::is_unique(&arc));
+    }
+
+    #[test]
+    fn compares_contents() {
+        let a: Arc<[u8], TrivialStrategy> = Arc::from(&[1, 2, 3, 1, 2][..]);
+        let view = ArcSlice::from(a);
+        assert_eq!(view.slice(..2), view.slice(3..));
+        assert!(view.slice(..2) < view.slice(1..));
+        let mut sorted: Vec<_> = (0..5).map(|i| view.slice(i..i + 1)).collect();
+        sorted.sort();
+        assert_eq!(sorted.concat(), [1, 1, 2, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn slice_out_of_bounds() {
+        let a: Arc<[u8], TrivialStrategy> = Arc::from(&[1, 2, 3][..]);
+        ArcSlice::from(a).slice(1..4);
+    }
+}
@@ -1,8 +1,12 @@
 use super::backdrop::BackdropStrategy;
 use arc_swap::RefCnt;
 
+use allocator_api2::boxed::Box;
+use core::ffi::c_void;
+
 use crate::arc::ArcInner;
-use crate::Arc;
+use crate::header::HeaderSliceWithLength;
+use crate::{Arc, ThinArc};
 
 unsafe impl<T, S> RefCnt for Arc<T, S>
 where
@@ -26,25 +30,24 @@ where
     }
 }
 
-// For now do not support ThinArc
-// use crate::{Arc, ThinArc};
-// use core::ffi::c_void;
-
-// unsafe impl<H, T> RefCnt for ThinArc<H, T> {
-//     type Base = c_void;
+unsafe impl<H, T, S> RefCnt for ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
+    type Base = c_void;
 
-//     #[inline]
-//     fn into_ptr(me: Self) -> *mut Self::Base {
-//         ThinArc::into_raw(me) as *mut _
-//     }
+    #[inline]
+    fn into_ptr(me: Self) -> *mut Self::Base {
+        ThinArc::into_raw(me) as *mut _
+    }
 
-//     #[inline]
-//     fn as_ptr(me: &Self) -> *mut Self::Base {
-//         ThinArc::as_ptr(me) as *mut _
-//     }
+    #[inline]
+    fn as_ptr(me: &Self) -> *mut Self::Base {
+        ThinArc::as_ptr(me) as *mut _
+    }
 
-//     #[inline]
-//     unsafe fn from_ptr(ptr: *const Self::Base) -> Self {
-//         ThinArc::from_raw(ptr)
-//     }
-// }
+    #[inline]
+    unsafe fn from_ptr(ptr: *const Self::Base) -> Self {
+        ThinArc::from_raw(ptr)
+    }
+}
@@ -1,15 +1,22 @@
-use alloc::boxed::Box;
+use allocator_api2::boxed::Box;
+use core::cmp::Ordering;
 use core::fmt;
+use core::hash::{Hash, Hasher};
 use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
 use core::ptr;
-use core::usize;
 
 extern crate backdrop;
 use crate::ArcInner;
 
 use self::backdrop::BackdropStrategy;
 
-use super::{Arc, ArcBorrow};
+use super::{Arc, ArcBorrow, ThinPointee};
+
+#[cfg(feature = "serde")]
+use serde::de::{self, Deserialize, Deserializer, EnumAccess, VariantAccess, Visitor};
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, Serializer};
 
 /// A tagged union that can represent `Arc<A>` or `Arc<B>` while only consuming a
 /// single word. The type is also `NonNull`, and thus can be stored in an Option
@@ -19,8 +26,28 @@ use super::{Arc, ArcBorrow};
 /// `enum ArcUnion<A, B> { First(Arc<A>), Second(Arc<B>)` but only takes up
 /// up a single word of stack space.
 ///
-/// This could probably be extended to support four types if necessary.
-pub struct ArcUnion<A, B, S>
+/// Besides sized types, `A` and `B` may be any [`ThinPointee`], such as a
+/// `HeaderSlice<HeaderWithLength<H>, [T]>` whose length is stored in its header:
+///
+/// ```
+/// use backdrop_arc::{Arc, ArcUnion, HeaderSlice, HeaderWithLength, TrivialStrategy};
+///
+/// type Node = HeaderSlice<HeaderWithLength<&'static str>, [u32]>;
+///
+/// let node: Arc<Node, TrivialStrategy> =
+///     Arc::from_header_and_slice(HeaderWithLength::new("node", 3), &[1, 2, 3]);
+/// let union: ArcUnion<u32, Node, TrivialStrategy> = ArcUnion::from_second(node);
+/// assert_eq!(std::mem::size_of_val(&union), std::mem::size_of::<usize>());
+/// assert_eq!(union.as_second().unwrap().slice, [1, 2, 3]);
+/// ```
+///
+/// There is no `From<Arc<A, S>>` or `From<Arc<B, S>>` impl,
+/// since the two would overlap when `A` and `B` are the same type.
+/// Use [`ArcUnion::from_first`] and [`ArcUnion::from_second`] instead,
+/// or convert from an [`ArcUnionOwned`], which says which of the two it holds.
+///
+/// See [`ArcUnion4`] for a union of four types.
+pub struct ArcUnion<A: ?Sized + ThinPointee, B: ?Sized + ThinPointee, S>
 where
     S: BackdropStrategy<Box<ArcInner<A>>>,
     S: BackdropStrategy<Box<ArcInner<B>>>,
@@ -31,20 +58,23 @@ where
     phantom_strategy: PhantomData<S>,
 }
 
-unsafe impl<A: Sync + Send, B: Send + Sync, S> Send for ArcUnion<A, B, S>
+unsafe impl<A: ?Sized + ThinPointee + Sync + Send, B: ?Sized + ThinPointee + Send + Sync, S> Send
+    for ArcUnion<A, B, S>
 where
     S: BackdropStrategy<Box<ArcInner<A>>>,
     S: BackdropStrategy<Box<ArcInner<B>>>,
 {
 }
-unsafe impl<A: Sync + Send, B: Send + Sync, S> Sync for ArcUnion<A, B, S>
+unsafe impl<A: ?Sized + ThinPointee + Sync + Send, B: ?Sized + ThinPointee + Send + Sync, S> Sync
+    for ArcUnion<A, B, S>
 where
     S: BackdropStrategy<Box<ArcInner<A>>>,
     S: BackdropStrategy<Box<ArcInner<B>>>,
 {
 }
 
-impl<A: PartialEq, B: PartialEq, S> PartialEq for ArcUnion<A, B, S>
+impl<A: ?Sized + ThinPointee + PartialEq, B: ?Sized + ThinPointee + PartialEq, S> PartialEq
+    for ArcUnion<A, B, S>
 where
     S: BackdropStrategy<Box<ArcInner<A>>>,
     S: BackdropStrategy<Box<ArcInner<B>>>,
@@ -59,14 +89,234 @@ where
     }
 }
 
+impl<A: ?Sized + ThinPointee + Eq, B: ?Sized + ThinPointee + Eq, S> Eq for ArcUnion<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+}
+
+impl<A: ?Sized + ThinPointee + Hash, B: ?Sized + ThinPointee + Hash, S> Hash for ArcUnion<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.borrow() {
+            ArcUnionBorrow::First(x) => {
+                state.write_u8(0);
+                (*x).hash(state)
+            }
+            ArcUnionBorrow::Second(x) => {
+                state.write_u8(1);
+                (*x).hash(state)
+            }
+        }
+    }
+}
+
+/// Values of the first type are ordered before values of the second type.
+impl<A: ?Sized + ThinPointee + PartialOrd, B: ?Sized + ThinPointee + PartialOrd, S> PartialOrd
+    for ArcUnion<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        use crate::ArcUnionBorrow::*;
+        match (self.borrow(), other.borrow()) {
+            (First(x), First(y)) => (*x).partial_cmp(&*y),
+            (Second(x), Second(y)) => (*x).partial_cmp(&*y),
+            (First(_), Second(_)) => Some(Ordering::Less),
+            (Second(_), First(_)) => Some(Ordering::Greater),
+        }
+    }
+}
+
+/// Values of the first type are ordered before values of the second type.
+impl<A: ?Sized + ThinPointee + Ord, B: ?Sized + ThinPointee + Ord, S> Ord for ArcUnion<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        use crate::ArcUnionBorrow::*;
+        match (self.borrow(), other.borrow()) {
+            (First(x), First(y)) => (*x).cmp(&*y),
+            (Second(x), Second(y)) => (*x).cmp(&*y),
+            (First(_), Second(_)) => Ordering::Less,
+            (Second(_), First(_)) => Ordering::Greater,
+        }
+    }
+}
+
+/// Serialized as an externally tagged enum with the variants `First` and `Second`,
+/// i.e. the same way as the equivalent `enum` would be.
+#[cfg(feature = "serde")]
+impl<A: ?Sized + ThinPointee + Serialize, B: ?Sized + ThinPointee + Serialize, S> Serialize
+    for ArcUnion<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        match self.borrow() {
+            ArcUnionBorrow::First(x) => {
+                serializer.serialize_newtype_variant("ArcUnion", 0, "First", &*x)
+            }
+            ArcUnionBorrow::Second(x) => {
+                serializer.serialize_newtype_variant("ArcUnion", 1, "Second", &*x)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+const VARIANTS: &[&str] = &["First", "Second"];
+
+/// The variant identifier of a serialized `ArcUnion`.
+#[cfg(feature = "serde")]
+enum Variant {
+    First,
+    Second,
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Variant {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct VariantVisitor;
+
+        impl<'de> Visitor<'de> for VariantVisitor {
+            type Value = Variant;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("`First` or `Second`")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Variant, E> {
+                match value {
+                    0 => Ok(Variant::First),
+                    1 => Ok(Variant::Second),
+                    _ => Err(E::invalid_value(
+                        de::Unexpected::Unsigned(value),
+                        &"variant index 0 <= i < 2",
+                    )),
+                }
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Variant, E> {
+                match value {
+                    "First" => Ok(Variant::First),
+                    "Second" => Ok(Variant::Second),
+                    _ => Err(E::unknown_variant(value, VARIANTS)),
+                }
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Variant, E> {
+                match value {
+                    b"First" => Ok(Variant::First),
+                    b"Second" => Ok(Variant::Second),
+                    _ => Err(E::invalid_value(de::Unexpected::Bytes(value), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(VariantVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, A: Deserialize<'de>, B: Deserialize<'de>, S> Deserialize<'de> for ArcUnion<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct UnionVisitor<A, B, S>(PhantomData<(A, B, S)>);
+
+        impl<'de, A: Deserialize<'de>, B: Deserialize<'de>, S> Visitor<'de> for UnionVisitor<A, B, S>
+        where
+            S: BackdropStrategy<Box<ArcInner<A>>>,
+            S: BackdropStrategy<Box<ArcInner<B>>>,
+        {
+            type Value = ArcUnion<A, B, S>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("enum ArcUnion")
+            }
+
+            fn visit_enum<E>(self, data: E) -> Result<Self::Value, E::Error>
+            where
+                E: EnumAccess<'de>,
+            {
+                match data.variant()? {
+                    (Variant::First, variant) => variant
+                        .newtype_variant()
+                        .map(|x| ArcUnion::from_first(Arc::new(x))),
+                    (Variant::Second, variant) => variant
+                        .newtype_variant()
+                        .map(|x| ArcUnion::from_second(Arc::new(x))),
+                }
+            }
+        }
+
+        deserializer.deserialize_enum("ArcUnion", VARIANTS, UnionVisitor(PhantomData))
+    }
+}
+
 /// This represents a borrow of an `ArcUnion`.
 #[derive(Debug)]
-pub enum ArcUnionBorrow<'a, A: 'a, B: 'a> {
+pub enum ArcUnionBorrow<'a, A: ?Sized + 'a, B: ?Sized + 'a> {
     First(ArcBorrow<'a, A>),
     Second(ArcBorrow<'a, B>),
 }
 
-impl<A, B, S> ArcUnion<A, B, S>
+/// This represents an owned `ArcUnion`, as returned by [`ArcUnion::into_enum`].
+pub enum ArcUnionOwned<A: ?Sized, B: ?Sized, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    First(Arc<A, S>),
+    Second(Arc<B, S>),
+}
+
+impl<A: ?Sized, B: ?Sized, S> Clone for ArcUnionOwned<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    fn clone(&self) -> Self {
+        match self {
+            ArcUnionOwned::First(x) => ArcUnionOwned::First(x.clone()),
+            ArcUnionOwned::Second(x) => ArcUnionOwned::Second(x.clone()),
+        }
+    }
+}
+
+impl<A: ?Sized + fmt::Debug, B: ?Sized + fmt::Debug, S> fmt::Debug for ArcUnionOwned<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArcUnionOwned::First(x) => f.debug_tuple("First").field(x).finish(),
+            ArcUnionOwned::Second(x) => f.debug_tuple("Second").field(x).finish(),
+        }
+    }
+}
+
+impl<A: ?Sized + ThinPointee, B: ?Sized + ThinPointee, S> ArcUnion<A, B, S>
 where
     S: BackdropStrategy<Box<ArcInner<A>>>,
     S: BackdropStrategy<Box<ArcInner<B>>>,
@@ -86,29 +336,37 @@ where
         this.p == other.p
     }
 
+    /// The pointer to the `ArcInner`, without the tag.
+    #[inline]
+    fn untagged(&self) -> *mut () {
+        ((self.p.as_ptr() as usize) & !0x1) as *mut ()
+    }
+
     /// Returns an enum representing a borrow of either A or B.
-    pub fn borrow(&self) -> ArcUnionBorrow<A, B> {
-        if self.is_first() {
-            let ptr = self.p.as_ptr() as *const A;
-            let borrow = unsafe { ArcBorrow::from_ref(&*ptr) };
-            ArcUnionBorrow::First(borrow)
-        } else {
-            let ptr = ((self.p.as_ptr() as usize) & !0x1) as *const B;
-            let borrow = unsafe { ArcBorrow::from_ref(&*ptr) };
-            ArcUnionBorrow::Second(borrow)
+    pub fn borrow(&self) -> ArcUnionBorrow<'_, A, B> {
+        unsafe {
+            if self.is_first() {
+                let inner = A::thicken(self.untagged());
+                ArcUnionBorrow::First(ArcBorrow::from_ref(&(*inner).data))
+            } else {
+                let inner = B::thicken(self.untagged());
+                ArcUnionBorrow::Second(ArcBorrow::from_ref(&(*inner).data))
+            }
         }
     }
 
     /// Creates an `ArcUnion` from an instance of the first type.
     #[inline]
     pub fn from_first(other: Arc<A, S>) -> Self {
-        unsafe { Self::new(Arc::into_raw(other) as *mut _) }
+        A::assert_thin(&other);
+        unsafe { Self::new(Arc::into_raw_inner(other) as *mut ()) }
     }
 
     /// Creates an `ArcUnion` from an instance of the second type.
     #[inline]
     pub fn from_second(other: Arc<B, S>) -> Self {
-        unsafe { Self::new(((Arc::into_raw(other) as usize) | 0x1) as *mut _) }
+        B::assert_thin(&other);
+        unsafe { Self::new(((Arc::into_raw_inner(other) as *mut () as usize) | 0x1) as *mut ()) }
     }
 
     /// Returns true if this `ArcUnion` contains the first type.
@@ -124,7 +382,7 @@ where
     }
 
     /// Returns a borrow of the first type if applicable, otherwise `None`.
-    pub fn as_first(&self) -> Option<ArcBorrow<A>> {
+    pub fn as_first(&self) -> Option<ArcBorrow<'_, A>> {
         match self.borrow() {
             ArcUnionBorrow::First(x) => Some(x),
             ArcUnionBorrow::Second(_) => None,
@@ -132,50 +390,612 @@ where
     }
 
     /// Returns a borrow of the second type if applicable, otherwise None.
-    pub fn as_second(&self) -> Option<ArcBorrow<B>> {
+    pub fn as_second(&self) -> Option<ArcBorrow<'_, B>> {
         match self.borrow() {
             ArcUnionBorrow::First(_) => None,
             ArcUnionBorrow::Second(x) => Some(x),
         }
     }
+
+    /// Takes the pointer out of `self` without touching the refcount.
+    #[inline]
+    fn into_untagged_ptr(self) -> *mut () {
+        let ptr = self.untagged();
+        mem::forget(self);
+        ptr
+    }
+
+    /// Converts into the first type if applicable, otherwise returns `self`.
+    ///
+    /// The reference is moved out of the union, so the refcount is not modified.
+    #[inline]
+    pub fn into_first(self) -> Result<Arc<A, S>, Self> {
+        if self.is_first() {
+            Ok(unsafe { Arc::from_raw_inner(A::thicken(self.into_untagged_ptr())) })
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Converts into the second type if applicable, otherwise returns `self`.
+    ///
+    /// The reference is moved out of the union, so the refcount is not modified.
+    #[inline]
+    pub fn into_second(self) -> Result<Arc<B, S>, Self> {
+        if self.is_second() {
+            Ok(unsafe { Arc::from_raw_inner(B::thicken(self.into_untagged_ptr())) })
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Converts into an enum owning either an `Arc<A>` or an `Arc<B>`.
+    ///
+    /// The reference is moved out of the union, so the refcount is not modified.
+    #[inline]
+    pub fn into_enum(self) -> ArcUnionOwned<A, B, S> {
+        if self.is_first() {
+            ArcUnionOwned::First(unsafe {
+                Arc::from_raw_inner(A::thicken(self.into_untagged_ptr()))
+            })
+        } else {
+            ArcUnionOwned::Second(unsafe {
+                Arc::from_raw_inner(B::thicken(self.into_untagged_ptr()))
+            })
+        }
+    }
+}
+
+impl<A: ?Sized + ThinPointee, B: ?Sized + ThinPointee, S> From<ArcUnionOwned<A, B, S>>
+    for ArcUnion<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    #[inline]
+    fn from(other: ArcUnionOwned<A, B, S>) -> Self {
+        match other {
+            ArcUnionOwned::First(x) => ArcUnion::from_first(x),
+            ArcUnionOwned::Second(x) => ArcUnion::from_second(x),
+        }
+    }
+}
+
+impl<A: ?Sized + ThinPointee, B: ?Sized + ThinPointee, S> From<ArcUnion<A, B, S>>
+    for ArcUnionOwned<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    #[inline]
+    fn from(other: ArcUnion<A, B, S>) -> Self {
+        other.into_enum()
+    }
 }
 
-impl<A, B, S> Clone for ArcUnion<A, B, S>
+impl<A: ?Sized + ThinPointee, B: ?Sized + ThinPointee, S> Clone for ArcUnion<A, B, S>
 where
     S: BackdropStrategy<Box<ArcInner<A>>>,
     S: BackdropStrategy<Box<ArcInner<B>>>,
 {
     fn clone(&self) -> Self {
+        // Bump the refcount through a transient `Arc`, and keep the tagged pointer as is.
+        unsafe {
+            if self.is_first() {
+                let transient =
+                    ManuallyDrop::new(Arc::<A, S>::from_raw_inner(A::thicken(self.untagged())));
+                mem::forget(Arc::clone(&transient));
+            } else {
+                let transient =
+                    ManuallyDrop::new(Arc::<B, S>::from_raw_inner(B::thicken(self.untagged())));
+                mem::forget(Arc::clone(&transient));
+            }
+            Self::new(self.p.as_ptr())
+        }
+    }
+}
+
+impl<A: ?Sized + ThinPointee, B: ?Sized + ThinPointee, S> Drop for ArcUnion<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    fn drop(&mut self) {
+        unsafe {
+            if self.is_first() {
+                drop(Arc::<A, S>::from_raw_inner(A::thicken(self.untagged())));
+            } else {
+                drop(Arc::<B, S>::from_raw_inner(B::thicken(self.untagged())));
+            }
+        }
+    }
+}
+
+impl<A: ?Sized + ThinPointee + fmt::Debug, B: ?Sized + ThinPointee + fmt::Debug, S> fmt::Debug
+    for ArcUnion<A, B, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.borrow(), f)
+    }
+}
+
+/// A tagged union that can represent `Arc<A>`, `Arc<B>`, `Arc<C>` or `Arc<D>`
+/// while only consuming a single word. Like [`ArcUnion`], it is `NonNull`.
+///
+/// This is functionally equivalent to
+/// `enum ArcUnion4<A, B, C, D> { First(Arc<A>), Second(Arc<B>), Third(Arc<C>), Fourth(Arc<D>) }`
+/// but only takes up a single word of stack space.
+///
+/// The two low bits of the pointer are used as the tag, which requires the
+/// `ArcInner` of each type to be aligned to at least 4 bytes. This is always
+/// the case on platforms where `AtomicUsize` is at least 4 bytes large.
+pub struct ArcUnion4<A, B, C, D, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+    S: BackdropStrategy<Box<ArcInner<C>>>,
+    S: BackdropStrategy<Box<ArcInner<D>>>,
+{
+    p: ptr::NonNull<()>,
+    phantom_a: PhantomData<A>,
+    phantom_b: PhantomData<B>,
+    phantom_c: PhantomData<C>,
+    phantom_d: PhantomData<D>,
+    phantom_strategy: PhantomData<S>,
+}
+
+unsafe impl<A: Sync + Send, B: Send + Sync, C: Send + Sync, D: Send + Sync, S> Send
+    for ArcUnion4<A, B, C, D, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+    S: BackdropStrategy<Box<ArcInner<C>>>,
+    S: BackdropStrategy<Box<ArcInner<D>>>,
+{
+}
+unsafe impl<A: Sync + Send, B: Send + Sync, C: Send + Sync, D: Send + Sync, S> Sync
+    for ArcUnion4<A, B, C, D, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+    S: BackdropStrategy<Box<ArcInner<C>>>,
+    S: BackdropStrategy<Box<ArcInner<D>>>,
+{
+}
+
+impl<A: PartialEq, B: PartialEq, C: PartialEq, D: PartialEq, S> PartialEq
+    for ArcUnion4<A, B, C, D, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+    S: BackdropStrategy<Box<ArcInner<C>>>,
+    S: BackdropStrategy<Box<ArcInner<D>>>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        use crate::ArcUnion4Borrow::*;
+        match (self.borrow(), other.borrow()) {
+            (First(x), First(y)) => x == y,
+            (Second(x), Second(y)) => x == y,
+            (Third(x), Third(y)) => x == y,
+            (Fourth(x), Fourth(y)) => x == y,
+            (_, _) => false,
+        }
+    }
+}
+
+/// This represents a borrow of an `ArcUnion4`.
+#[derive(Debug)]
+pub enum ArcUnion4Borrow<'a, A: 'a, B: 'a, C: 'a, D: 'a> {
+    First(ArcBorrow<'a, A>),
+    Second(ArcBorrow<'a, B>),
+    Third(ArcBorrow<'a, C>),
+    Fourth(ArcBorrow<'a, D>),
+}
+
+/// The bits of the pointer used as the tag of an `ArcUnion4`.
+const TAG_MASK: usize = 0x3;
+
+impl<A, B, C, D, S> ArcUnion4<A, B, C, D, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+    S: BackdropStrategy<Box<ArcInner<C>>>,
+    S: BackdropStrategy<Box<ArcInner<D>>>,
+{
+    /// Tags the data pointer of an `Arc<T>` with `tag`.
+    #[inline]
+    fn tagged<T>(other: Arc<T, S>, tag: usize) -> Self
+    where
+        S: BackdropStrategy<Box<ArcInner<T>>>,
+    {
+        // The data of an `ArcInner<T>` is aligned to the `ArcInner` itself,
+        // since it follows the `count` (and is padded to its own alignment).
+        assert!(
+            mem::align_of::<ArcInner<T>>() > TAG_MASK,
+            "ArcUnion4 requires an ArcInner alignment of at least 4",
+        );
+        T::assert_thin(&other);
+        let ptr = Arc::into_raw(other) as usize;
+        debug_assert_eq!(ptr & TAG_MASK, 0);
+        unsafe {
+            ArcUnion4 {
+                p: ptr::NonNull::new_unchecked((ptr | tag) as *mut ()),
+                phantom_a: PhantomData,
+                phantom_b: PhantomData,
+                phantom_c: PhantomData,
+                phantom_d: PhantomData,
+                phantom_strategy: PhantomData,
+            }
+        }
+    }
+
+    #[inline]
+    fn tag(&self) -> usize {
+        self.p.as_ptr() as usize & TAG_MASK
+    }
+
+    /// Returns true if the two values are pointer-equal.
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.p == other.p
+    }
+
+    /// Returns an enum representing a borrow of either A, B, C or D.
+    pub fn borrow(&self) -> ArcUnion4Borrow<'_, A, B, C, D> {
+        let ptr = (self.p.as_ptr() as usize) & !TAG_MASK;
+        unsafe {
+            match self.tag() {
+                0 => ArcUnion4Borrow::First(ArcBorrow::from_ref(&*(ptr as *const A))),
+                1 => ArcUnion4Borrow::Second(ArcBorrow::from_ref(&*(ptr as *const B))),
+                2 => ArcUnion4Borrow::Third(ArcBorrow::from_ref(&*(ptr as *const C))),
+                _ => ArcUnion4Borrow::Fourth(ArcBorrow::from_ref(&*(ptr as *const D))),
+            }
+        }
+    }
+
+    /// Creates an `ArcUnion4` from an instance of the first type.
+    #[inline]
+    pub fn from_first(other: Arc<A, S>) -> Self {
+        Self::tagged(other, 0)
+    }
+
+    /// Creates an `ArcUnion4` from an instance of the second type.
+    #[inline]
+    pub fn from_second(other: Arc<B, S>) -> Self {
+        Self::tagged(other, 1)
+    }
+
+    /// Creates an `ArcUnion4` from an instance of the third type.
+    #[inline]
+    pub fn from_third(other: Arc<C, S>) -> Self {
+        Self::tagged(other, 2)
+    }
+
+    /// Creates an `ArcUnion4` from an instance of the fourth type.
+    #[inline]
+    pub fn from_fourth(other: Arc<D, S>) -> Self {
+        Self::tagged(other, 3)
+    }
+
+    /// Returns true if this `ArcUnion4` contains the first type.
+    #[inline]
+    pub fn is_first(&self) -> bool {
+        self.tag() == 0
+    }
+
+    /// Returns true if this `ArcUnion4` contains the second type.
+    #[inline]
+    pub fn is_second(&self) -> bool {
+        self.tag() == 1
+    }
+
+    /// Returns true if this `ArcUnion4` contains the third type.
+    #[inline]
+    pub fn is_third(&self) -> bool {
+        self.tag() == 2
+    }
+
+    /// Returns true if this `ArcUnion4` contains the fourth type.
+    #[inline]
+    pub fn is_fourth(&self) -> bool {
+        self.tag() == 3
+    }
+
+    /// Returns a borrow of the first type if applicable, otherwise `None`.
+    pub fn as_first(&self) -> Option<ArcBorrow<'_, A>> {
+        match self.borrow() {
+            ArcUnion4Borrow::First(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Returns a borrow of the second type if applicable, otherwise `None`.
+    pub fn as_second(&self) -> Option<ArcBorrow<'_, B>> {
+        match self.borrow() {
+            ArcUnion4Borrow::Second(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Returns a borrow of the third type if applicable, otherwise `None`.
+    pub fn as_third(&self) -> Option<ArcBorrow<'_, C>> {
         match self.borrow() {
-            ArcUnionBorrow::First(x) => ArcUnion::from_first(x.clone_arc()),
-            ArcUnionBorrow::Second(x) => ArcUnion::from_second(x.clone_arc()),
+            ArcUnion4Borrow::Third(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Returns a borrow of the fourth type if applicable, otherwise `None`.
+    pub fn as_fourth(&self) -> Option<ArcBorrow<'_, D>> {
+        match self.borrow() {
+            ArcUnion4Borrow::Fourth(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl<A, B, C, D, S> Clone for ArcUnion4<A, B, C, D, S>
+where
+    S: BackdropStrategy<Box<ArcInner<A>>>,
+    S: BackdropStrategy<Box<ArcInner<B>>>,
+    S: BackdropStrategy<Box<ArcInner<C>>>,
+    S: BackdropStrategy<Box<ArcInner<D>>>,
+{
+    fn clone(&self) -> Self {
+        match self.borrow() {
+            ArcUnion4Borrow::First(x) => ArcUnion4::from_first(x.clone_arc()),
+            ArcUnion4Borrow::Second(x) => ArcUnion4::from_second(x.clone_arc()),
+            ArcUnion4Borrow::Third(x) => ArcUnion4::from_third(x.clone_arc()),
+            ArcUnion4Borrow::Fourth(x) => ArcUnion4::from_fourth(x.clone_arc()),
         }
     }
 }
 
-impl<A, B, S> Drop for ArcUnion<A, B, S>
+impl<A, B, C, D, S> Drop for ArcUnion4<A, B, C, D, S>
 where
     S: BackdropStrategy<Box<ArcInner<A>>>,
     S: BackdropStrategy<Box<ArcInner<B>>>,
+    S: BackdropStrategy<Box<ArcInner<C>>>,
+    S: BackdropStrategy<Box<ArcInner<D>>>,
 {
     fn drop(&mut self) {
         match self.borrow() {
-            ArcUnionBorrow::First(x) => unsafe {
+            ArcUnion4Borrow::First(x) => unsafe {
+                let _ = Arc::<_, S>::from_raw(&*x);
+            },
+            ArcUnion4Borrow::Second(x) => unsafe {
+                let _ = Arc::<_, S>::from_raw(&*x);
+            },
+            ArcUnion4Borrow::Third(x) => unsafe {
                 let _ = Arc::<_, S>::from_raw(&*x);
             },
-            ArcUnionBorrow::Second(x) => unsafe {
+            ArcUnion4Borrow::Fourth(x) => unsafe {
                 let _ = Arc::<_, S>::from_raw(&*x);
             },
         }
     }
 }
 
-impl<A: fmt::Debug, B: fmt::Debug, S> fmt::Debug for ArcUnion<A, B, S>
+impl<A: fmt::Debug, B: fmt::Debug, C: fmt::Debug, D: fmt::Debug, S> fmt::Debug
+    for ArcUnion4<A, B, C, D, S>
 where
     S: BackdropStrategy<Box<ArcInner<A>>>,
     S: BackdropStrategy<Box<ArcInner<B>>>,
+    S: BackdropStrategy<Box<ArcInner<C>>>,
+    S: BackdropStrategy<Box<ArcInner<D>>>,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Debug::fmt(&self.borrow(), f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::TrivialStrategy;
+    use super::{ArcUnion, ArcUnion4, ArcUnionOwned};
+    use alloc::string::String;
+    use core::mem;
+
+    use crate::{Arc, HeaderSlice, HeaderWithLength};
+
+    type Union4 = ArcUnion4<u8, u64, String, (), TrivialStrategy>;
+
+    #[test]
+    fn union_is_one_word() {
+        assert_eq!(
+            mem::size_of::<Option<ArcUnion<u8, u64, TrivialStrategy>>>(),
+            mem::size_of::<usize>()
+        );
+        assert_eq!(mem::size_of::<Option<Union4>>(), mem::size_of::<usize>());
+    }
+
+    #[test]
+    fn union_into() {
+        type Union = ArcUnion<u8, String, TrivialStrategy>;
+
+        let arc = Arc::<String, TrivialStrategy>::new(String::from("second"));
+        let union = Union::from_second(arc.clone());
+        let union = union.into_first().unwrap_err();
+        let second = union.into_second().unwrap();
+        assert!(Arc::ptr_eq(&arc, &second));
+        assert_eq!(Arc::count(&arc), 2);
+
+        let union = Union::from(ArcUnionOwned::Second(second));
+        match union.into_enum() {
+            ArcUnionOwned::Second(x) => assert!(Arc::ptr_eq(&arc, &x)),
+            ArcUnionOwned::First(_) => panic!("expected the second type"),
+        }
+        assert_eq!(Arc::count(&arc), 1);
+
+        let union = Union::from_first(Arc::new(1));
+        match ArcUnionOwned::from(union.clone()) {
+            ArcUnionOwned::First(x) => assert_eq!(*x, 1),
+            ArcUnionOwned::Second(_) => panic!("expected the first type"),
+        }
+        assert_eq!(*union.into_first().unwrap(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Length needs to be correct")]
+    fn union_checks_header_length() {
+        type Slice = HeaderSlice<HeaderWithLength<u8>, [u64]>;
+
+        let arc: Arc<Slice, TrivialStrategy> =
+            Arc::from_header_and_slice(HeaderWithLength::new(0, 100_000), &[1]);
+        ArcUnion::<Slice, u8, TrivialStrategy>::from_first(arc);
+    }
+
+    #[test]
+    fn union_traits() {
+        use alloc::collections::BTreeSet;
+        use alloc::format;
+        use alloc::vec;
+        use alloc::vec::Vec;
+        use core::hash::{Hash, Hasher};
+
+        type Union = ArcUnion<u8, String, TrivialStrategy>;
+
+        let mut unions = vec![
+            Union::from_second(Arc::new(String::from("b"))),
+            Union::from_first(Arc::new(2)),
+            Union::from_second(Arc::new(String::from("a"))),
+            Union::from_first(Arc::new(1)),
+        ];
+        unions.sort();
+        let sorted: Vec<_> = unions.iter().map(|x| format!("{:?}", x)).collect();
+        assert_eq!(
+            sorted,
+            [
+                "First(ArcBorrow(1))",
+                "First(ArcBorrow(2))",
+                "Second(ArcBorrow(\"a\"))",
+                "Second(ArcBorrow(\"b\"))"
+            ]
+        );
+
+        let set: BTreeSet<_> = unions.iter().cloned().chain(unions.clone()).collect();
+        assert_eq!(set.len(), 4);
+
+        // A hasher that records what was written, so that equal values can be compared.
+        #[derive(Default)]
+        struct Recorder(Vec<u8>);
+        impl Hasher for Recorder {
+            fn finish(&self) -> u64 {
+                0
+            }
+            fn write(&mut self, bytes: &[u8]) {
+                self.0.extend_from_slice(bytes)
+            }
+        }
+        let hash = |union: &Union| {
+            let mut recorder = Recorder::default();
+            union.hash(&mut recorder);
+            recorder.0
+        };
+        assert_eq!(hash(&unions[0]), hash(&Union::from_first(Arc::new(1))));
+        assert_ne!(hash(&unions[0]), hash(&unions[1]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn union_serde() {
+        use serde_test::{assert_tokens, Token};
+
+        type Union = ArcUnion<u8, String, TrivialStrategy>;
+
+        assert_tokens(
+            &Union::from_first(Arc::new(1)),
+            &[
+                Token::NewtypeVariant {
+                    name: "ArcUnion",
+                    variant: "First",
+                },
+                Token::U8(1),
+            ],
+        );
+        assert_tokens(
+            &Union::from_second(Arc::new(String::from("two"))),
+            &[
+                Token::NewtypeVariant {
+                    name: "ArcUnion",
+                    variant: "Second",
+                },
+                Token::String("two"),
+            ],
+        );
+    }
+
+    #[test]
+    fn union_unsized() {
+        type Node = HeaderSlice<HeaderWithLength<&'static str>, [u32]>;
+        type Union = ArcUnion<u64, Node, TrivialStrategy>;
+
+        let node: Arc<Node, TrivialStrategy> =
+            Arc::from_header_and_slice(HeaderWithLength::new("node", 3), &[1, 2, 3]);
+        let union = Union::from_second(node.clone());
+        assert_eq!(mem::size_of_val(&union), mem::size_of::<usize>());
+        assert_eq!(Arc::count(&node), 2);
+
+        let second = union.as_second().unwrap();
+        assert_eq!(second.header.header, "node");
+        assert_eq!(second.slice, [1, 2, 3]);
+
+        let cloned = union.clone();
+        assert_eq!(Arc::count(&node), 3);
+        assert_eq!(union, cloned);
+        assert_ne!(union, Union::from_first(Arc::new(1)));
+        drop(cloned);
+
+        let leaf = Union::from_first(Arc::new(7));
+        assert_eq!(*leaf.as_first().unwrap(), 7);
+        assert!(leaf.as_second().is_none());
+
+        let back = union.into_second().unwrap();
+        assert!(Arc::ptr_eq(&back, &node));
+        assert_eq!(Arc::count(&node), 2);
+    }
+
+    #[test]
+    fn union4_variants() {
+        let first = Union4::from_first(Arc::new(1));
+        let second = Union4::from_second(Arc::new(2));
+        let third = Union4::from_third(Arc::new(String::from("three")));
+        let fourth = Union4::from_fourth(Arc::new(()));
+
+        assert!(first.is_first() && !first.is_second());
+        assert!(second.is_second() && !second.is_third());
+        assert!(third.is_third() && !third.is_fourth());
+        assert!(fourth.is_fourth() && !fourth.is_first());
+
+        assert_eq!(*first.as_first().unwrap(), 1);
+        assert_eq!(*second.as_second().unwrap(), 2);
+        assert_eq!(*third.as_third().unwrap(), "three");
+        assert!(fourth.as_fourth().is_some());
+        assert!(first.as_second().is_none());
+        assert!(third.as_fourth().is_none());
+    }
+
+    #[test]
+    fn union4_clone_and_eq() {
+        let arc = Arc::<String, TrivialStrategy>::new(String::from("shared"));
+        let union = Union4::from_third(arc.clone());
+        assert_eq!(Arc::count(&arc), 2);
+
+        let cloned = union.clone();
+        assert_eq!(Arc::count(&arc), 3);
+        assert!(ArcUnion4::ptr_eq(&union, &cloned));
+        assert_eq!(union, cloned);
+        assert_ne!(union, Union4::from_third(Arc::new(String::from("other"))));
+        assert_ne!(
+            Union4::from_first(Arc::new(2)),
+            Union4::from_second(Arc::new(2))
+        );
+
+        drop(union);
+        drop(cloned);
+        assert_eq!(Arc::count(&arc), 1);
+    }
+}
@@ -0,0 +1,621 @@
+use alloc::alloc::{handle_alloc_error, Layout};
+use allocator_api2::alloc::{Allocator, Global};
+use allocator_api2::boxed::Box;
+use core::cmp;
+use core::fmt;
+use core::iter::Extend;
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
+use core::ops::{Deref, DerefMut};
+use core::ptr::{self, addr_of_mut, NonNull};
+
+extern crate backdrop;
+use self::backdrop::{BackdropStrategy, TrivialStrategy};
+
+use super::{AllocError, Arc, ArcInner, HeaderSlice, UniqueArc};
+
+/// A growable `HeaderSlice<H, [T]>` which is built directly inside the allocation
+/// of the `Arc` it will become.
+///
+/// Collecting items of unknown length into a `Vec` and then converting that into an `Arc`
+/// means copying every item into a second allocation.
+/// An `ArcSliceBuilder` instead grows its own `ArcInner` (reallocating it, like a `Vec` would),
+/// and [`finish`](ArcSliceBuilder::finish) merely shrinks it to fit,
+/// so the items are never copied by the builder itself.
+///
+/// ```
+/// use backdrop_arc::{Arc, ArcSliceBuilder, HeaderSlice, TrivialStrategy};
+///
+/// let mut builder: ArcSliceBuilder<&str, u32, TrivialStrategy> = ArcSliceBuilder::new("batch");
+/// builder.push(1);
+/// builder.extend((2..10).filter(|x| x % 2 == 0));
+/// builder.header = "numbers";
+///
+/// let arc: Arc<HeaderSlice<&str, [u32]>, TrivialStrategy> = builder.finish().shareable();
+/// assert_eq!(arc.header, "numbers");
+/// assert_eq!(arc.slice, [1, 2, 4, 6, 8]);
+/// ```
+///
+/// Use `()` as header to build an `Arc<[T], S>` with [`finish_slice`](ArcSliceBuilder::finish_slice).
+pub struct ArcSliceBuilder<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    // Allocated for `cap` elements, with an initialized refcount, header and first `len` elements.
+    ptr: NonNull<ArcInner<HeaderSlice<H, [T]>>>,
+    len: usize,
+    cap: usize,
+    phantom: PhantomData<(H, T)>,
+    strategy: PhantomData<fn() -> S>,
+}
+
+unsafe impl<H: Send, T: Send, S> Send for ArcSliceBuilder<H, T, S> where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>
+{
+}
+unsafe impl<H: Sync, T: Sync, S> Sync for ArcSliceBuilder<H, T, S> where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>
+{
+}
+
+impl<H, T, S> ArcSliceBuilder<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    /// Creates an empty builder with the given header.
+    ///
+    /// This already allocates room for the refcount and header, but not for any elements.
+    #[inline]
+    pub fn new(header: H) -> Self {
+        Self::with_capacity(header, 0)
+    }
+
+    /// Creates an empty builder with the given header and room for at least `capacity` elements.
+    pub fn with_capacity(header: H, capacity: usize) -> Self {
+        let cap = if mem::size_of::<T>() == 0 {
+            usize::MAX
+        } else {
+            capacity
+        };
+        let ptr = Arc::<_, S>::allocate_for_header_and_slice(cap, &Global);
+
+        // Safety: `ptr` was just allocated, and the header was not initialized yet
+        unsafe { ptr::write(addr_of_mut!((*ptr.as_ptr()).data.header), header) };
+
+        ArcSliceBuilder {
+            ptr,
+            len: 0,
+            cap,
+            phantom: PhantomData,
+            strategy: PhantomData,
+        }
+    }
+
+    /// The number of elements pushed so far.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no elements were pushed so far.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of elements the builder can hold without reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Appends an element to the slice.
+    #[inline]
+    pub fn push(&mut self, item: T) {
+        if self.len == self.cap {
+            self.reserve(1);
+        }
+
+        // Safety: there is room for at least one more element
+        unsafe { ptr::write(self.slice_ptr().add(self.len), item) };
+        self.len += 1;
+    }
+
+    /// Appends all elements of a slice.
+    pub fn extend_from_slice(&mut self, items: &[T])
+    where
+        T: Copy,
+    {
+        self.reserve(items.len());
+
+        // Safety: there is room for `items.len()` more elements, in a separate allocation
+        unsafe {
+            ptr::copy_nonoverlapping(items.as_ptr(), self.slice_ptr().add(self.len), items.len())
+        };
+        self.len += items.len();
+    }
+
+    /// Reserves room for at least `additional` more elements.
+    ///
+    /// Like `Vec::reserve`, this may reserve more to avoid frequent reallocations.
+    pub fn reserve(&mut self, additional: usize) {
+        if additional > self.cap - self.len {
+            let cap = self.grown_capacity(additional).expect("capacity overflow");
+            let layout = Self::layout(cap).expect("capacity overflow");
+
+            // Safety: `cap` is larger than the current capacity
+            unsafe { self.grow_to(cap, layout) }.unwrap_or_else(|_| handle_alloc_error(layout))
+        }
+    }
+
+    /// Reserves room for at least `additional` more elements,
+    /// returning an error if allocation fails.
+    ///
+    /// On failure, the builder is left unchanged.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        if additional > self.cap - self.len {
+            let cap = self.grown_capacity(additional).ok_or(AllocError)?;
+            let layout = Self::layout(cap)?;
+
+            // Safety: `cap` is larger than the current capacity
+            unsafe { self.grow_to(cap, layout) }?;
+        }
+        Ok(())
+    }
+
+    /// Turns the builder into a `UniqueArc`, shrinking its allocation to fit.
+    ///
+    /// Call `shareable()` on the result to get an `Arc`.
+    pub fn finish(self) -> UniqueArc<HeaderSlice<H, [T]>, S> {
+        let this = ManuallyDrop::new(self);
+        let old_layout = Self::current_layout(this.cap);
+        let new_layout = Self::current_layout(this.len);
+
+        let mut mem = this.ptr.cast::<u8>();
+        if new_layout != old_layout {
+            // Safety: `mem` was allocated with `old_layout`, which is at least as large as `new_layout`
+            mem = unsafe { Global.shrink(mem, old_layout, new_layout) }
+                .unwrap_or_else(|_| handle_alloc_error(new_layout))
+                .cast();
+        }
+        let inner = Arc::<_, S>::header_and_slice_ptr(mem.as_ptr(), this.len);
+
+        // Safety:
+        // - the allocation now has the exact layout of an `ArcInner` for `len` elements
+        // - the refcount, header and all `len` elements are initialized
+        // - nothing else refers to the allocation, so the `Arc` is unique
+        unsafe { UniqueArc::from_arc(Arc::from_raw_inner(inner)) }
+    }
+
+    fn slice_ptr(&self) -> *mut T {
+        // Safety: `ptr` is a valid pointer, so this can't go out of bounds
+        unsafe { addr_of_mut!((*self.ptr.as_ptr()).data.slice) as *mut T }
+    }
+
+    fn layout(cap: usize) -> Result<Layout, AllocError> {
+        Arc::<HeaderSlice<H, [T]>, S>::inner_layout_for_header_and_slice(cap)
+    }
+
+    /// The layout for at most the current capacity, which is known to be valid.
+    fn current_layout(cap: usize) -> Layout {
+        Self::layout(cap).expect("capacity was allocated before")
+    }
+
+    fn grown_capacity(&self, additional: usize) -> Option<usize> {
+        let required = self.len.checked_add(additional)?;
+        Some(cmp::max(required, cmp::max(self.cap * 2, 4)))
+    }
+
+    /// # Safety
+    ///
+    /// `cap` must be larger than the current capacity, and `layout` must be its layout.
+    unsafe fn grow_to(&mut self, cap: usize, layout: Layout) -> Result<(), AllocError> {
+        let old_layout = Self::current_layout(self.cap);
+        let mem = Global
+            .grow(self.ptr.cast(), old_layout, layout)
+            .map_err(|_| AllocError)?
+            .cast::<u8>();
+
+        self.ptr = NonNull::new_unchecked(Arc::<_, S>::header_and_slice_ptr(mem.as_ptr(), cap));
+        self.cap = cap;
+        Ok(())
+    }
+}
+
+impl<T, S> ArcSliceBuilder<(), T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<(), [T]>>>>,
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+{
+    /// Turns the builder into a `UniqueArc<[T], S>`, shrinking its allocation to fit.
+    ///
+    /// ```
+    /// use backdrop_arc::{Arc, ArcSliceBuilder, TrivialStrategy};
+    ///
+    /// let mut builder = ArcSliceBuilder::<(), String, TrivialStrategy>::with_capacity((), 2);
+    /// builder.push("hello".to_string());
+    /// builder.push("world".to_string());
+    ///
+    /// let arc: Arc<[String], TrivialStrategy> = builder.finish_slice().shareable();
+    /// assert_eq!(arc.join(" "), "hello world");
+    /// ```
+    pub fn finish_slice(self) -> UniqueArc<[T], S> {
+        let arc: Arc<[T], S> = self.finish().shareable().into();
+        // Safety: We just created an `Arc`, so it's unique.
+        unsafe { UniqueArc::from_arc(arc) }
+    }
+}
+
+impl<H, T, S> Drop for ArcSliceBuilder<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    fn drop(&mut self) {
+        unsafe {
+            // Safety: the header and first `len` elements are initialized,
+            // and the allocation was made with the layout for `cap` elements
+            ptr::drop_in_place(addr_of_mut!((*self.ptr.as_ptr()).data.header));
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.slice_ptr(), self.len));
+            Global.deallocate(self.ptr.cast(), Self::current_layout(self.cap));
+        }
+    }
+}
+
+impl<H, T, S> Deref for ArcSliceBuilder<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    type Target = HeaderSlice<H, [T]>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        let inner = Arc::<_, S>::header_and_slice_ptr(self.ptr.as_ptr() as *mut u8, self.len);
+        // Safety: the header and first `len` elements are initialized
+        unsafe { &(*inner).data }
+    }
+}
+
+impl<H, T, S> DerefMut for ArcSliceBuilder<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let inner = Arc::<_, S>::header_and_slice_ptr(self.ptr.as_ptr() as *mut u8, self.len);
+        // Safety: the header and first `len` elements are initialized, and we have unique access
+        unsafe { &mut (*inner).data }
+    }
+}
+
+impl<H, T, S> Extend<T> for ArcSliceBuilder<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        // If the iterator panics, `Drop` cleans up the elements pushed so far
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<H: fmt::Debug, T: fmt::Debug, S> fmt::Debug for ArcSliceBuilder<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArcSliceBuilder")
+            .field("header", &self.header)
+            .field("slice", &&self.slice)
+            .finish()
+    }
+}
+
+/// A growable string which is built directly inside the allocation of the `Arc<str, S>`
+/// (or `Arc<HeaderSlice<H, str>, S>`) it will become.
+///
+/// It implements [`fmt::Write`], so it can be the target of `write!`.
+/// To create an `Arc<str, S>` from format arguments directly, use [`arc_format!`](crate::arc_format).
+///
+/// ```
+/// use backdrop_arc::{Arc, ArcStrBuilder, HeaderSlice, TrivialStrategy};
+/// use std::fmt::Write;
+///
+/// let mut builder: ArcStrBuilder<u32, TrivialStrategy> = ArcStrBuilder::new(404);
+/// builder.push_str("GET ");
+/// write!(builder, "/{}/{}", "users", 42).unwrap();
+///
+/// let arc: Arc<HeaderSlice<u32, str>, TrivialStrategy> = builder.finish().shareable();
+/// assert_eq!(arc.header, 404);
+/// assert_eq!(&arc.slice, "GET /users/42");
+/// ```
+pub struct ArcStrBuilder<H, S> {
+    // The strategy of the bytes is irrelevant, since they never become an `Arc` of their own.
+    bytes: ArcSliceBuilder<H, u8, TrivialStrategy>,
+    strategy: PhantomData<fn() -> S>,
+}
+
+impl<H, S> ArcStrBuilder<H, S> {
+    /// Creates an empty builder with the given header.
+    #[inline]
+    pub fn new(header: H) -> Self {
+        Self::with_capacity(header, 0)
+    }
+
+    /// Creates an empty builder with the given header and room for at least `capacity` bytes.
+    #[inline]
+    pub fn with_capacity(header: H, capacity: usize) -> Self {
+        ArcStrBuilder {
+            bytes: ArcSliceBuilder::with_capacity(header, capacity),
+            strategy: PhantomData,
+        }
+    }
+
+    /// The length of the string so far, in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Whether the string is empty so far.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// The number of bytes the builder can hold without reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    /// Reserves room for at least `additional` more bytes.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.bytes.reserve(additional)
+    }
+
+    /// Reserves room for at least `additional` more bytes,
+    /// returning an error if allocation fails.
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        self.bytes.try_reserve(additional)
+    }
+
+    /// Appends a string slice.
+    #[inline]
+    pub fn push_str(&mut self, string: &str) {
+        self.bytes.extend_from_slice(string.as_bytes())
+    }
+
+    /// Appends a single character.
+    #[inline]
+    pub fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Turns the builder into a `UniqueArc`, shrinking its allocation to fit.
+    ///
+    /// Call `shareable()` on the result to get an `Arc`.
+    pub fn finish(self) -> UniqueArc<HeaderSlice<H, str>, S>
+    where
+        S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, str>>>>,
+    {
+        let bytes = self.bytes.finish().shareable();
+
+        // Safety: `ArcInner` and `HeaderSlice` are `repr(C)`, `str` has the same layout as `[u8]`,
+        //         and the bytes are valid UTF-8 since only `str`s were pushed.
+        //         We just created the `Arc`, so it's unique.
+        unsafe { UniqueArc::from_arc(Arc::from_raw_inner(Arc::into_raw_inner(bytes) as _)) }
+    }
+}
+
+impl<S> ArcStrBuilder<(), S> {
+    /// Turns the builder into a `UniqueArc<str, S>`, shrinking its allocation to fit.
+    ///
+    /// ```
+    /// use backdrop_arc::{Arc, ArcStrBuilder, TrivialStrategy};
+    ///
+    /// let mut builder = ArcStrBuilder::<(), TrivialStrategy>::new(());
+    /// builder.push_str("hello");
+    /// builder.push(' ');
+    /// builder.push_str("world");
+    ///
+    /// let arc: Arc<str, TrivialStrategy> = builder.finish_str().shareable();
+    /// assert_eq!(&*arc, "hello world");
+    /// ```
+    pub fn finish_str(self) -> UniqueArc<str, S>
+    where
+        S: BackdropStrategy<Box<ArcInner<str>>>,
+    {
+        let bytes = self.bytes.finish().shareable();
+
+        // Safety: `ArcInner` and `HeaderSlice` are `repr(C)`, `HeaderSlice<(), [u8]>` has the same
+        //         layout as `str`, and the bytes are valid UTF-8 since only `str`s were pushed.
+        //         We just created the `Arc`, so it's unique.
+        unsafe {
+            UniqueArc::from_arc(Arc::from_raw_inner(
+                Arc::into_raw_inner(bytes) as *mut ArcInner<str>
+            ))
+        }
+    }
+}
+
+impl<H, S> Deref for ArcStrBuilder<H, S> {
+    type Target = HeaderSlice<H, str>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        let bytes: &HeaderSlice<H, [u8]> = &self.bytes;
+        // Safety: `HeaderSlice` is `repr(C)`, `str` has the same layout as `[u8]`,
+        //         and the bytes are valid UTF-8 since only `str`s were pushed.
+        unsafe { &*(bytes as *const HeaderSlice<H, [u8]> as *const HeaderSlice<H, str>) }
+    }
+}
+
+impl<H, S> DerefMut for ArcStrBuilder<H, S> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let bytes: &mut HeaderSlice<H, [u8]> = &mut self.bytes;
+        // Safety: as in `deref`. `&mut str` only allows changes which keep it valid UTF-8.
+        unsafe { &mut *(bytes as *mut HeaderSlice<H, [u8]> as *mut HeaderSlice<H, str>) }
+    }
+}
+
+impl<H, S> fmt::Write for ArcStrBuilder<H, S> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.push(c);
+        Ok(())
+    }
+}
+
+impl<H: fmt::Debug, S> fmt::Debug for ArcStrBuilder<H, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArcStrBuilder")
+            .field("header", &self.header)
+            .field("slice", &&self.slice)
+            .finish()
+    }
+}
+
+impl<S> Arc<str, S>
+where
+    S: BackdropStrategy<Box<ArcInner<str>>>,
+{
+    /// Creates an `Arc<str, S>` from format arguments,
+    /// formatting directly into the allocation of the `Arc` instead of into a `String` first.
+    ///
+    /// This is usually called through the [`arc_format!`](crate::arc_format) macro.
+    pub fn from_fmt(args: fmt::Arguments) -> Self {
+        // Without any arguments to format, we know the exact length up front
+        let capacity = args.as_str().map_or(0, str::len);
+        let mut builder = ArcStrBuilder::<(), S>::with_capacity((), capacity);
+        fmt::Write::write_fmt(&mut builder, args)
+            .expect("a formatting trait implementation returned an error");
+        builder.finish_str().shareable()
+    }
+}
+
+/// Creates an `Arc<str, S>` using interpolation of runtime expressions,
+/// like `format!` does for a `String`.
+///
+/// The string is formatted directly into the allocation of the `Arc`, see [`Arc::from_fmt`].
+///
+/// ```
+/// use backdrop_arc::{arc_format, Arc, TrivialStrategy};
+///
+/// let user = "ferris";
+/// let message: Arc<str, TrivialStrategy> = arc_format!("hello, {}! {:>4}", user, 42);
+/// assert_eq!(&*message, "hello, ferris!   42");
+/// ```
+#[macro_export]
+macro_rules! arc_format {
+    ($($arg:tt)*) => {
+        $crate::Arc::from_fmt(format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::TrivialStrategy;
+    use super::{ArcSliceBuilder, ArcStrBuilder};
+    use alloc::string::{String, ToString};
+    use core::cell::Cell;
+    use core::fmt::Write;
+
+    use crate::{Arc, HeaderSlice};
+
+    #[test]
+    fn grows_and_finishes() {
+        let mut builder: ArcSliceBuilder<u8, String, TrivialStrategy> = ArcSliceBuilder::new(7);
+        assert!(builder.is_empty());
+        assert_eq!(builder.capacity(), 0);
+        for i in 0..100 {
+            builder.push(i.to_string());
+        }
+        assert_eq!(builder.len(), 100);
+        assert!(builder.capacity() >= 100);
+        builder.slice[0].push('!');
+
+        let arc: Arc<HeaderSlice<u8, [String]>, TrivialStrategy> = builder.finish().shareable();
+        assert_eq!(arc.header, 7);
+        assert_eq!(arc.slice.len(), 100);
+        assert_eq!(arc.slice[0], "0!");
+        assert_eq!(arc.slice[99], "99");
+
+        let mut builder = ArcSliceBuilder::<(), u64, TrivialStrategy>::with_capacity((), 10);
+        builder.extend(0..3);
+        builder.try_reserve(1000).unwrap();
+        let arc: Arc<[u64], TrivialStrategy> = builder.finish_slice().shareable();
+        assert_eq!(*arc, [0, 1, 2]);
+
+        let empty = ArcSliceBuilder::<(), u64, TrivialStrategy>::with_capacity((), 10);
+        assert!(empty.finish_slice().is_empty());
+    }
+
+    #[test]
+    fn drops_unfinished() {
+        struct DropCounter<'a>(&'a Cell<usize>);
+        impl<'a> Drop for DropCounter<'a> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        let mut builder: ArcSliceBuilder<DropCounter, DropCounter, TrivialStrategy> =
+            ArcSliceBuilder::new(DropCounter(&drops));
+        builder.extend((0..5).map(|_| DropCounter(&drops)));
+        drop(builder);
+        assert_eq!(drops.get(), 6);
+
+        let mut builder: ArcSliceBuilder<(), (), TrivialStrategy> = ArcSliceBuilder::new(());
+        builder.extend((0..5).map(|_| ()));
+        assert_eq!(builder.capacity(), usize::MAX);
+        assert_eq!(builder.finish_slice().len(), 5);
+        let mut builder: ArcSliceBuilder<(), (), TrivialStrategy> = ArcSliceBuilder::new(());
+        builder.push(());
+        assert!(builder.try_reserve(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn str_builder() {
+        let mut builder: ArcStrBuilder<u8, TrivialStrategy> = ArcStrBuilder::with_capacity(1, 4);
+        builder.push_str("abc");
+        builder.push('é');
+        write!(builder, "{}-{:?}", 12, "x").unwrap();
+        builder.slice.make_ascii_uppercase();
+        builder.header += 1;
+        assert_eq!(builder.len(), "ABCé12-\"X\"".len());
+
+        let arc: Arc<HeaderSlice<u8, str>, TrivialStrategy> = builder.finish().shareable();
+        assert_eq!(arc.header, 2);
+        assert_eq!(&arc.slice, "ABCé12-\"X\"");
+
+        let empty = ArcStrBuilder::<(), TrivialStrategy>::new(()).finish_str();
+        assert_eq!(&*empty, "");
+    }
+
+    #[test]
+    fn arc_format() {
+        let name = "world";
+        let arc: Arc<str, TrivialStrategy> = arc_format!("hello {name}, {}", 1 + 1);
+        assert_eq!(&*arc, "hello world, 2");
+
+        let arc: Arc<str, TrivialStrategy> = arc_format!("constant");
+        assert_eq!(&*arc, "constant");
+
+        let long = "x".repeat(1000);
+        let arc: Arc<str, TrivialStrategy> = arc_format!("{long}{long}");
+        assert_eq!(arc.len(), 2000);
+    }
+}
@@ -0,0 +1,138 @@
+use super::backdrop::BackdropStrategy;
+use bytes::Bytes;
+
+use allocator_api2::boxed::Box;
+
+use crate::arc::ArcInner;
+use crate::{Arc, ArcSlice, HeaderSlice};
+
+/// Keeps an `Arc` alive as the owner of the bytes of a `Bytes`.
+struct BytesOwner<T: ?Sized, S>(Arc<T, S>)
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>;
+
+impl<S> AsRef<[u8]> for BytesOwner<str, S>
+where
+    S: BackdropStrategy<Box<ArcInner<str>>>,
+{
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl<H, S> AsRef<[u8]> for BytesOwner<HeaderSlice<H, [u8]>, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [u8]>>>>,
+{
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0.slice
+    }
+}
+
+impl<S> From<Arc<[u8], S>> for Bytes
+where
+    S: BackdropStrategy<Box<ArcInner<[u8]>>> + 'static,
+{
+    /// Wraps the `Arc` without copying its contents.
+    /// Dropping the last clone of the `Bytes` drops the `Arc`.
+    #[inline]
+    fn from(arc: Arc<[u8], S>) -> Self {
+        Bytes::from_owner(arc)
+    }
+}
+
+impl<S> From<Arc<str, S>> for Bytes
+where
+    S: BackdropStrategy<Box<ArcInner<str>>> + 'static,
+{
+    /// Wraps the `Arc` without copying its contents.
+    /// Dropping the last clone of the `Bytes` drops the `Arc`.
+    #[inline]
+    fn from(arc: Arc<str, S>) -> Self {
+        Bytes::from_owner(BytesOwner(arc))
+    }
+}
+
+impl<H, S> From<Arc<HeaderSlice<H, [u8]>, S>> for Bytes
+where
+    H: Send + Sync + 'static,
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [u8]>>>> + 'static,
+{
+    /// Wraps the slice of the `Arc` without copying it.
+    /// Dropping the last clone of the `Bytes` drops the `Arc` (including its header).
+    #[inline]
+    fn from(arc: Arc<HeaderSlice<H, [u8]>, S>) -> Self {
+        Bytes::from_owner(BytesOwner(arc))
+    }
+}
+
+impl<S> From<ArcSlice<u8, S>> for Bytes
+where
+    S: BackdropStrategy<Box<ArcInner<[u8]>>> + 'static,
+{
+    /// Wraps the viewed bytes without copying them.
+    /// Dropping the last clone of the `Bytes` drops the view.
+    #[inline]
+    fn from(slice: ArcSlice<u8, S>) -> Self {
+        Bytes::from_owner(slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bytes;
+    use crate::backdrop::BackdropStrategy;
+    use allocator_api2::boxed::Box;
+    use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    use crate::{Arc, ArcInner, ArcSlice, HeaderSlice};
+
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingStrategy;
+
+    impl<T: ?Sized> BackdropStrategy<Box<ArcInner<T>>> for CountingStrategy {
+        fn execute(droppable: Box<ArcInner<T>>) {
+            DROPPED.fetch_add(1, SeqCst);
+            drop(droppable)
+        }
+    }
+
+    #[test]
+    fn into_bytes() {
+        let arc: Arc<[u8], CountingStrategy> = Arc::from(&b"body"[..]);
+        let bytes = Bytes::from(arc.clone());
+        assert_eq!(bytes, &b"body"[..]);
+        assert_eq!(bytes.as_ptr(), arc[..].as_ptr());
+        let clone = bytes.slice(1..);
+        drop(bytes);
+        drop(arc);
+        assert_eq!(DROPPED.load(SeqCst), 0);
+        drop(clone);
+        assert_eq!(DROPPED.load(SeqCst), 1);
+
+        let string: Arc<str, CountingStrategy> = Arc::from("text");
+        let ptr = string.as_bytes().as_ptr();
+        let bytes = Bytes::from(string);
+        assert_eq!(bytes, "text");
+        assert_eq!(bytes.as_ptr(), ptr);
+        drop(bytes);
+        assert_eq!(DROPPED.load(SeqCst), 2);
+
+        let header: Arc<HeaderSlice<u32, [u8]>, CountingStrategy> =
+            Arc::from_header_and_slice(200, b"ok");
+        let bytes = Bytes::from(header);
+        assert_eq!(bytes, "ok");
+        drop(bytes);
+        assert_eq!(DROPPED.load(SeqCst), 3);
+
+        let view = ArcSlice::from(Arc::<[u8], CountingStrategy>::from(&b"GET /"[..]));
+        let bytes = Bytes::from(view.slice(4..));
+        drop(view);
+        assert_eq!(bytes, "/");
+        drop(bytes);
+        assert_eq!(DROPPED.load(SeqCst), 4);
+    }
+}
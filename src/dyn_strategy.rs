@@ -0,0 +1,430 @@
+use alloc::alloc::Layout;
+use allocator_api2::alloc::{AllocError, Allocator, Global};
+use allocator_api2::boxed::Box;
+use core::cmp;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::{self, NonNull};
+
+extern crate backdrop;
+use self::backdrop::{BackdropStrategy, TrivialStrategy};
+
+use super::{Arc, ArcInner, CopyOnWriteAllocator, UniqueArc};
+
+/// A drop strategy that is chosen at runtime, and can be stored per allocation by [`DynStrategy`].
+///
+/// Any (static) [`BackdropStrategy`] can be turned into one using [`StaticStrategy`],
+/// but you can also implement it yourself, for instance to pick a queue at runtime.
+pub trait DynBackdropStrategy: Sync {
+    /// Drop the (type-erased) value in whatever way this strategy wants.
+    fn execute(&self, droppable: alloc::boxed::Box<dyn Send>);
+}
+
+/// Adapts a (static, zero-sized) [`BackdropStrategy`] to a [`DynBackdropStrategy`].
+///
+/// ```
+/// use backdrop_arc::{DynBackdropStrategy, StaticStrategy, TrivialStrategy};
+///
+/// // Or `StaticStrategy<ThreadStrategy>`, `StaticStrategy<TrashQueueStrategy>`, etc.
+/// static INLINE: StaticStrategy<TrivialStrategy> = StaticStrategy::new();
+/// let strategy: &'static dyn DynBackdropStrategy = &INLINE;
+/// ```
+pub struct StaticStrategy<S>(PhantomData<fn() -> S>);
+
+impl<S> StaticStrategy<S> {
+    /// Creates the adapter for the strategy `S`.
+    pub const fn new() -> Self {
+        StaticStrategy(PhantomData)
+    }
+}
+
+impl<S> Default for StaticStrategy<S> {
+    fn default() -> Self {
+        StaticStrategy::new()
+    }
+}
+
+impl<S> fmt::Debug for StaticStrategy<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StaticStrategy")
+    }
+}
+
+impl<S> DynBackdropStrategy for StaticStrategy<S>
+where
+    S: BackdropStrategy<alloc::boxed::Box<dyn Send>>,
+{
+    #[inline]
+    fn execute(&self, droppable: alloc::boxed::Box<dyn Send>) {
+        S::execute(droppable)
+    }
+}
+
+/// The strategy used by arcs allocated in [`DynStrategyAlloc`] for which no strategy was chosen:
+/// drop inline.
+static INLINE: StaticStrategy<TrivialStrategy> = StaticStrategy::new();
+
+/// The prefix [`DynStrategyAlloc`] puts in front of every allocation.
+type Prefix = &'static dyn DynBackdropStrategy;
+
+/// An [`Allocator`] which stores a [`DynBackdropStrategy`] in front of every allocation.
+///
+/// This is how [`DynStrategy`] stores the strategy per allocation:
+/// the `ArcInner` of an `Arc<T, DynStrategy, DynStrategyAlloc>` is preceded by the strategy chosen
+/// in [`Arc::new_with_strategy`] (or the inline strategy if the `Arc` was constructed some other way).
+/// The memory itself comes from the [`Global`] allocator.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DynStrategyAlloc;
+
+impl DynStrategyAlloc {
+    /// Returns the layout including the prefix, and the offset of the user's memory in it.
+    #[inline]
+    fn prefixed_layout(layout: Layout) -> Result<(Layout, usize), AllocError> {
+        let (layout, offset) = Layout::new::<Prefix>()
+            .extend(layout)
+            .map_err(|_| AllocError)?;
+        Ok((layout.pad_to_align(), offset))
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by a `DynStrategyAlloc`
+    #[inline]
+    unsafe fn prefix(ptr: *const u8) -> *mut Prefix {
+        // Safety: the offset of the user's memory is at least the size of the prefix,
+        //         and a multiple of its alignment
+        //         (any padding required by a higher alignment of the user's memory comes first).
+        ptr.sub(mem::size_of::<Prefix>()) as *mut Prefix
+    }
+
+    /// Moves an allocation to a new layout, keeping its prefix.
+    ///
+    /// `realloc` grows or shrinks the underlying `Global` allocation
+    /// (including the prefix) when the prefix stays at the same offset.
+    ///
+    /// # Safety
+    ///
+    /// Same as `Allocator::grow` or `Allocator::shrink` (whichever `realloc` is).
+    unsafe fn reallocate<F>(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+        realloc: F,
+    ) -> Result<NonNull<[u8]>, AllocError>
+    where
+        F: FnOnce(NonNull<u8>, Layout, Layout) -> Result<NonNull<[u8]>, AllocError>,
+    {
+        let (old_full, old_offset) = DynStrategyAlloc::prefixed_layout(old_layout)
+            .expect("layout was valid when allocating");
+        let (new_full, new_offset) = DynStrategyAlloc::prefixed_layout(new_layout)?;
+
+        let new_ptr = if old_offset == new_offset {
+            // The prefix is moved along with the rest of the allocation
+            let mem = NonNull::new_unchecked(ptr.as_ptr().sub(old_offset));
+            let mem = realloc(mem, old_full, new_full)?.cast::<u8>();
+            NonNull::new_unchecked(mem.as_ptr().add(new_offset))
+        } else {
+            // A different alignment moves the user's memory relative to the start of the allocation
+            let new_ptr = self.allocate(new_layout)?.cast::<u8>();
+            let size = cmp::min(old_layout.size(), new_layout.size());
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), size);
+            *DynStrategyAlloc::prefix(new_ptr.as_ptr()) = *DynStrategyAlloc::prefix(ptr.as_ptr());
+            self.deallocate(ptr, old_layout);
+            new_ptr
+        };
+        Ok(NonNull::slice_from_raw_parts(new_ptr, new_layout.size()))
+    }
+}
+
+unsafe impl Allocator for DynStrategyAlloc {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let (full, offset) = DynStrategyAlloc::prefixed_layout(layout)?;
+        let mem = Global.allocate(full)?.cast::<u8>();
+        unsafe {
+            // Safety: `offset` is within the allocation made for `full`
+            let ptr = mem.as_ptr().add(offset);
+            ptr::write(DynStrategyAlloc::prefix(ptr), &INLINE as Prefix);
+            Ok(NonNull::slice_from_raw_parts(
+                NonNull::new_unchecked(ptr),
+                layout.size(),
+            ))
+        }
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let (full, offset) =
+            DynStrategyAlloc::prefixed_layout(layout).expect("layout was valid when allocating");
+        Global.deallocate(NonNull::new_unchecked(ptr.as_ptr().sub(offset)), full)
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.reallocate(ptr, old_layout, new_layout, |mem, old, new| {
+            Global.grow(mem, old, new)
+        })
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let new_ptr = self.grow(ptr, old_layout, new_layout)?;
+        // Not left to `Global.grow_zeroed`, which would keep the padding after the old memory as is
+        let grown = new_layout.size() - old_layout.size();
+        ptr::write_bytes(
+            (new_ptr.as_ptr() as *mut u8).add(old_layout.size()),
+            0,
+            grown,
+        );
+        Ok(new_ptr)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.reallocate(ptr, old_layout, new_layout, |mem, old, new| {
+            Global.shrink(mem, old, new)
+        })
+    }
+}
+
+/// Copies made by [`Arc::make_mut`] and [`Arc::make_unique`] keep the strategy of the original.
+impl CopyOnWriteAllocator for DynStrategyAlloc {
+    #[inline]
+    unsafe fn copy_allocation_state(&self, from: NonNull<u8>, to: NonNull<u8>, _layout: Layout) {
+        *DynStrategyAlloc::prefix(to.as_ptr()) = *DynStrategyAlloc::prefix(from.as_ptr());
+    }
+}
+
+/// A [`BackdropStrategy`] which is chosen at runtime, and stored in the allocation of each `Arc`.
+///
+/// This allows a single `Arc<T, DynStrategy, DynStrategyAlloc>` type (or [`DynArc<T>`])
+/// to be dropped inline in one place and in the background in another,
+/// without making every function that handles it generic over the strategy.
+///
+/// The strategy is stored in front of the `ArcInner` by the [`DynStrategyAlloc`] allocator,
+/// and is chosen when constructing using [`Arc::new_with_strategy`].
+/// Arcs constructed in any other way are dropped inline.
+///
+/// Since the value is handed to the strategy as a `Box<dyn Send>`,
+/// it requires `T: Send + Sync + 'static`.
+///
+/// ```
+/// use backdrop_arc::{DynArc, DynBackdropStrategy, LeakStrategy, StaticStrategy};
+///
+/// static LEAK: StaticStrategy<LeakStrategy> = StaticStrategy::new();
+///
+/// let inline: DynArc<String> = DynArc::new_with_strategy("dropped inline".into(), DynArc::<String>::INLINE);
+/// let leaked: DynArc<String> = DynArc::new_with_strategy("leaked".into(), &LEAK);
+///
+/// // Both have the same type:
+/// let arcs = vec![inline, leaked];
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DynStrategy;
+
+/// An `Arc` using a [`DynStrategy`], whose drop strategy is chosen at runtime.
+pub type DynArc<T> = Arc<T, DynStrategy, DynStrategyAlloc>;
+
+impl<T> BackdropStrategy<Box<ArcInner<T>, DynStrategyAlloc>> for DynStrategy
+where
+    T: ?Sized + Send + Sync + 'static,
+{
+    #[inline]
+    fn execute(droppable: Box<ArcInner<T>, DynStrategyAlloc>) {
+        // Safety: the `ArcInner` was allocated by a `DynStrategyAlloc`,
+        //         whose prefix is always initialized
+        let strategy = unsafe { *DynStrategyAlloc::prefix(&*droppable as *const _ as *const u8) };
+        strategy.execute(alloc::boxed::Box::new(droppable))
+    }
+}
+
+impl<T> Arc<T, DynStrategy, DynStrategyAlloc>
+where
+    T: ?Sized + Send + Sync + 'static,
+{
+    /// The strategy that drops inline, used when no other strategy was chosen.
+    pub const INLINE: &'static dyn DynBackdropStrategy = &INLINE;
+
+    /// Returns the strategy that will be used to drop this `Arc`'s contents.
+    #[inline]
+    pub fn strategy(this: &Self) -> &'static dyn DynBackdropStrategy {
+        // Safety: the `ArcInner` was allocated by a `DynStrategyAlloc`
+        unsafe { *DynStrategyAlloc::prefix(this.ptr() as *const u8) }
+    }
+}
+
+impl<T> Arc<T, DynStrategy, DynStrategyAlloc>
+where
+    T: Send + Sync + 'static,
+{
+    /// Construct an `Arc` which will be dropped using `strategy`.
+    #[inline]
+    pub fn new_with_strategy(data: T, strategy: &'static dyn DynBackdropStrategy) -> Self {
+        UniqueArc::new_with_strategy(data, strategy).shareable()
+    }
+}
+
+impl<T> UniqueArc<T, DynStrategy, DynStrategyAlloc>
+where
+    T: ?Sized + Send + Sync + 'static,
+{
+    /// Change the strategy that will be used to drop this `UniqueArc`'s contents.
+    #[inline]
+    pub fn set_strategy(this: &mut Self, strategy: &'static dyn DynBackdropStrategy) {
+        // Safety: the `ArcInner` was allocated by a `DynStrategyAlloc`,
+        //         and we have unique access to it
+        unsafe { *DynStrategyAlloc::prefix(this.0.ptr() as *const u8) = strategy }
+    }
+}
+
+impl<T> UniqueArc<T, DynStrategy, DynStrategyAlloc>
+where
+    T: Send + Sync + 'static,
+{
+    /// Construct a `UniqueArc` which will be dropped using `strategy`.
+    #[inline]
+    pub fn new_with_strategy(data: T, strategy: &'static dyn DynBackdropStrategy) -> Self {
+        let mut this = UniqueArc::new_in(data, DynStrategyAlloc);
+        UniqueArc::set_strategy(&mut this, strategy);
+        this
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::LeakStrategy;
+    use super::{DynArc, DynBackdropStrategy, DynStrategyAlloc, StaticStrategy};
+    use alloc::alloc::Layout;
+    use alloc::boxed::Box;
+    use alloc::string::String;
+    use alloc::vec;
+    use allocator_api2::alloc::Allocator;
+    use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    use crate::{Arc, UniqueArc};
+
+    struct Counting(AtomicUsize);
+
+    impl DynBackdropStrategy for Counting {
+        fn execute(&self, droppable: Box<dyn Send>) {
+            self.0.fetch_add(1, SeqCst);
+            drop(droppable)
+        }
+    }
+
+    #[test]
+    fn strategy_is_stored_per_allocation() {
+        static COUNTING: Counting = Counting(AtomicUsize::new(0));
+        static LEAK: StaticStrategy<LeakStrategy> = StaticStrategy::new();
+
+        let counted: DynArc<String> = Arc::new_with_strategy(String::from("counted"), &COUNTING);
+        let leaked: DynArc<String> = Arc::new_with_strategy(String::from("leaked"), &LEAK);
+        let inline: DynArc<String> = Arc::new_in(String::from("inline"), Default::default());
+        assert!(core::ptr::eq(
+            DynArc::strategy(&inline) as *const _ as *const u8,
+            DynArc::<String>::INLINE as *const _ as *const u8
+        ));
+
+        let arcs = vec![counted.clone(), leaked, inline];
+        drop(arcs);
+        assert_eq!(COUNTING.0.load(SeqCst), 0);
+        assert_eq!(*counted, "counted");
+        drop(counted);
+        assert_eq!(COUNTING.0.load(SeqCst), 1);
+    }
+
+    #[test]
+    fn unwrap_and_set_strategy() {
+        static COUNTING: Counting = Counting(AtomicUsize::new(0));
+
+        let arc: DynArc<[u64; 4]> = Arc::new_with_strategy([1, 2, 3, 4], &COUNTING);
+        assert_eq!(Arc::try_unwrap(arc), Ok([1, 2, 3, 4]));
+
+        #[repr(align(64))]
+        #[derive(Debug, PartialEq)]
+        struct Aligned(u8);
+        let arc: DynArc<Aligned> = Arc::new_with_strategy(Aligned(7), &COUNTING);
+        assert_eq!(&*arc as *const Aligned as usize % 64, 0);
+        assert_eq!(Arc::try_unwrap(arc), Ok(Aligned(7)));
+
+        let mut unique = UniqueArc::new_in(String::from("unique"), Default::default());
+        UniqueArc::set_strategy(&mut unique, &COUNTING);
+        let arc: DynArc<String> = unique.shareable();
+        drop(arc);
+        assert_eq!(COUNTING.0.load(SeqCst), 1);
+    }
+
+    #[test]
+    fn reallocating_keeps_the_strategy() {
+        static COUNTING: Counting = Counting(AtomicUsize::new(0));
+        let counting = &COUNTING as &dyn DynBackdropStrategy as *const _ as *const u8;
+        let strategy = |ptr: *mut u8| unsafe {
+            *DynStrategyAlloc::prefix(ptr) as *const dyn DynBackdropStrategy as *const u8
+        };
+
+        let layout = Layout::from_size_align(8, 8).unwrap();
+        let grown = Layout::from_size_align(64, 8).unwrap();
+        let aligned = Layout::from_size_align(128, 64).unwrap();
+        unsafe {
+            let ptr = DynStrategyAlloc.allocate(layout).unwrap().cast::<u8>();
+            *DynStrategyAlloc::prefix(ptr.as_ptr()) = &COUNTING;
+            ptr.as_ptr().write_bytes(7, 8);
+
+            let ptr = DynStrategyAlloc
+                .grow(ptr, layout, grown)
+                .unwrap()
+                .cast::<u8>();
+            assert_eq!(strategy(ptr.as_ptr()), counting);
+            assert_eq!(*ptr.as_ptr().add(7), 7);
+
+            // With a higher alignment, the memory moves relative to the start of the allocation
+            let ptr = DynStrategyAlloc
+                .grow_zeroed(ptr, grown, aligned)
+                .unwrap()
+                .cast::<u8>();
+            assert_eq!(ptr.as_ptr() as usize % 64, 0);
+            assert_eq!(strategy(ptr.as_ptr()), counting);
+            assert_eq!(*ptr.as_ptr().add(7), 7);
+            assert_eq!(*ptr.as_ptr().add(64), 0);
+
+            let ptr = DynStrategyAlloc
+                .shrink(ptr, aligned, layout)
+                .unwrap()
+                .cast::<u8>();
+            assert_eq!(strategy(ptr.as_ptr()), counting);
+            assert_eq!(*ptr.as_ptr(), 7);
+            DynStrategyAlloc.deallocate(ptr, layout);
+        }
+    }
+
+    #[test]
+    fn copies_keep_the_strategy() {
+        static COUNTING: Counting = Counting(AtomicUsize::new(0));
+
+        let mut arc: DynArc<String> = Arc::new_with_strategy(String::from("a"), &COUNTING);
+        let shared = arc.clone();
+        Arc::make_mut(&mut arc).push('!');
+        assert!(!Arc::ptr_eq(&arc, &shared));
+        assert!(core::ptr::eq(
+            DynArc::strategy(&arc) as *const _ as *const u8,
+            &COUNTING as *const Counting as *const u8
+        ));
+
+        let mut unique = arc.clone();
+        UniqueArc::set_strategy(Arc::make_unique(&mut unique), &COUNTING);
+        drop((arc, shared, unique));
+        assert_eq!(COUNTING.0.load(SeqCst), 3);
+    }
+}
@@ -0,0 +1,308 @@
+//! Sharing [`OffsetArc`]s with C and C++.
+//!
+//! An [`OffsetArc<T, S>`](OffsetArc) is a single (non-null) pointer to its data,
+//! so C and C++ can read the data directly.
+//! To also let them hold on to (clones of) it, they need to be able to change its refcount.
+//! The [`offset_arc_ffi!`](crate::offset_arc_ffi) macro generates `extern "C"`
+//! retain, release and count functions for one `OffsetArc<T, S>` type,
+//! together with the C declarations of those functions.
+//!
+//! Releasing the last reference from C or C++ runs the backdrop strategy `S` on the Rust side,
+//! just like dropping the last `Arc` in Rust does.
+//! So a large structure released from e.g. a render thread can still be dropped in the background.
+//!
+//! No `.h` file is generated automatically: the declarations are only available as strings in Rust.
+//! Turn them into a header with [`c_header`], and write it out yourself,
+//! or (with the `std` feature) use [`write_c_header`], for instance from a `build.rs`
+//! of a crate depending on the one that invokes the macro, or from a test which keeps a checked-in header up to date.
+//!
+//! ```
+//! use backdrop_arc::{Arc, OffsetArc, TrivialStrategy};
+//!
+//! pub struct Style {
+//!     pub color: u32,
+//! }
+//!
+//! backdrop_arc::offset_arc_ffi! {
+//!     /// The C declarations for `OffsetArc<Style, TrivialStrategy>`, called `Style` in C.
+//!     pub const STYLE_HEADER: OffsetArc<Style, TrivialStrategy> as "Style" {
+//!         retain: style_retain,
+//!         release: style_release,
+//!         count: style_count,
+//!     }
+//! }
+//!
+//! let header = backdrop_arc::ffi::c_header("STYLE_H", &[STYLE_HEADER]);
+//! assert!(header.contains("void style_release(const Style *ptr);"));
+//!
+//! // Hand a reference to C...
+//! let style: OffsetArc<Style, TrivialStrategy> = Arc::into_raw_offset(Arc::new(Style { color: 0xff0000 }));
+//! let ptr = OffsetArc::into_raw(style);
+//! unsafe {
+//!     // ...which can read it, clone it, and release its clones.
+//!     style_retain(ptr);
+//!     assert_eq!(style_count(ptr), 2);
+//!     style_release(ptr);
+//!     style_release(ptr);
+//! }
+//! ```
+
+use alloc::string::String;
+use allocator_api2::boxed::Box;
+use core::mem::{self, ManuallyDrop};
+
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+pub use core::ffi::c_void;
+
+use super::{Arc, ArcInner, OffsetArc, ThinPointee};
+
+/// Increments the refcount of the `OffsetArc<T, S>` whose data `ptr` points to.
+///
+/// Does nothing if `ptr` is null.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to the data of a live `OffsetArc<T, S>`.
+#[inline]
+pub unsafe fn retain<T: ?Sized + ThinPointee, S>(ptr: *const c_void)
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    if !ptr.is_null() {
+        let transient = ManuallyDrop::new(OffsetArc::<T, S>::from_raw(ptr));
+        mem::forget((*transient).clone());
+    }
+}
+
+/// Decrements the refcount of the `OffsetArc<T, S>` whose data `ptr` points to.
+/// If this was the last reference, its contents are dropped using the strategy `S`.
+///
+/// Does nothing if `ptr` is null.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to the data of a live `OffsetArc<T, S>`,
+/// and the reference it represents must not be used again afterwards.
+#[inline]
+pub unsafe fn release<T: ?Sized + ThinPointee, S>(ptr: *const c_void)
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    if !ptr.is_null() {
+        drop(OffsetArc::<T, S>::from_raw(ptr));
+    }
+}
+
+/// Returns the refcount of the `OffsetArc<T, S>` whose data `ptr` points to,
+/// or zero if `ptr` is null.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to the data of a live `OffsetArc<T, S>`.
+#[inline]
+pub unsafe fn count<T: ?Sized + ThinPointee, S>(ptr: *const c_void) -> usize
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    if ptr.is_null() {
+        0
+    } else {
+        let transient = ManuallyDrop::new(OffsetArc::<T, S>::from_raw(ptr));
+        transient.with_arc(Arc::count)
+    }
+}
+
+/// Wraps the declarations generated by [`offset_arc_ffi!`](crate::offset_arc_ffi)
+/// in a complete C header, using `guard` as the include guard.
+pub fn c_header(guard: &str, declarations: &[&str]) -> String {
+    let mut header = String::new();
+    header.push_str("#ifndef ");
+    header.push_str(guard);
+    header.push_str("\n#define ");
+    header.push_str(guard);
+    header.push_str("\n\n#include <stddef.h>\n\n#ifdef __cplusplus\nextern \"C\" {\n#endif\n");
+    for declarations in declarations {
+        header.push('\n');
+        header.push_str(declarations);
+    }
+    header.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n#endif\n");
+    header
+}
+
+/// Writes the header built by [`c_header`] to `path`.
+///
+/// The file is only written if its contents changed,
+/// so that calling this from a `build.rs` does not cause needless rebuilds of C code including it.
+///
+/// ```
+/// # use backdrop_arc::{OffsetArc, TrivialStrategy};
+/// # pub struct Style;
+/// # backdrop_arc::offset_arc_ffi! {
+/// #     pub const STYLE_HEADER: OffsetArc<Style, TrivialStrategy> as "Style" {
+/// #         retain: style_retain,
+/// #         release: style_release,
+/// #         count: style_count,
+/// #     }
+/// # }
+/// // E.g. in a `build.rs`, with `path` inside `OUT_DIR` or the C sources:
+/// let path = std::env::temp_dir().join("backdrop_arc_doctest_style.h");
+/// backdrop_arc::ffi::write_c_header(&path, "STYLE_H", &[STYLE_HEADER])?;
+/// assert!(std::fs::read_to_string(&path)?.contains("size_t style_count(const Style *ptr);"));
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn write_c_header<P: AsRef<std::path::Path>>(
+    path: P,
+    guard: &str,
+    declarations: &[&str],
+) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let header = c_header(guard, declarations);
+    match std::fs::read(path) {
+        Ok(existing) if existing == header.as_bytes() => Ok(()),
+        _ => std::fs::write(path, header),
+    }
+}
+
+/// Generates `extern "C"` functions to retain, release and count an `OffsetArc<T, S>` from C,
+/// and a `const` containing their C declarations.
+///
+/// The data is passed to C as a `const Name *`, where `Name` is the C name of the type.
+/// The declarations include a forward declaration of `struct Name`,
+/// so C code can either define the struct itself (to read the data directly) or treat it as opaque.
+/// Use [`ffi::c_header`](crate::ffi::c_header) to turn the declarations into a complete header,
+/// and [`ffi::write_c_header`](crate::ffi::write_c_header) to write it to a `.h` file.
+///
+/// See the [`ffi`](crate::ffi) module for an example.
+#[macro_export]
+macro_rules! offset_arc_ffi {
+    (
+        $(#[$meta:meta])*
+        $vis:vis const $header:ident: OffsetArc<$ty:ty, $strategy:ty> as $c_name:literal {
+            retain: $retain:ident,
+            release: $release:ident,
+            count: $count:ident $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis const $header: &str = concat!(
+            "typedef struct ", $c_name, " ", $c_name, ";\n",
+            "void ", stringify!($retain), "(const ", $c_name, " *ptr);\n",
+            "void ", stringify!($release), "(const ", $c_name, " *ptr);\n",
+            "size_t ", stringify!($count), "(const ", $c_name, " *ptr);\n",
+        );
+
+        #[doc = concat!("Increments the refcount of the `", $c_name, "` that `ptr` points to.")]
+        ///
+        /// # Safety
+        ///
+        /// `ptr` must be null or point to the data of a live `OffsetArc`.
+        #[no_mangle]
+        pub unsafe extern "C" fn $retain(ptr: *const $crate::ffi::c_void) {
+            $crate::ffi::retain::<$ty, $strategy>(ptr)
+        }
+
+        #[doc = concat!("Decrements the refcount of the `", $c_name, "` that `ptr` points to,")]
+        /// dropping it using its backdrop strategy if this was the last reference.
+        ///
+        /// # Safety
+        ///
+        /// `ptr` must be null or point to the data of a live `OffsetArc`,
+        /// and the reference it represents must not be used again afterwards.
+        #[no_mangle]
+        pub unsafe extern "C" fn $release(ptr: *const $crate::ffi::c_void) {
+            $crate::ffi::release::<$ty, $strategy>(ptr)
+        }
+
+        #[doc = concat!("Returns the refcount of the `", $c_name, "` that `ptr` points to.")]
+        ///
+        /// # Safety
+        ///
+        /// `ptr` must be null or point to the data of a live `OffsetArc`.
+        #[no_mangle]
+        pub unsafe extern "C" fn $count(ptr: *const $crate::ffi::c_void) -> usize {
+            $crate::ffi::count::<$ty, $strategy>(ptr)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::BackdropStrategy;
+    use super::c_header;
+    use alloc::string::String;
+    use allocator_api2::boxed::Box;
+    use core::ptr;
+    use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    use crate::{Arc, ArcInner, HeaderSlice, HeaderWithLength, OffsetArc};
+
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingStrategy;
+
+    impl<T: ?Sized> BackdropStrategy<Box<ArcInner<T>>> for CountingStrategy {
+        fn execute(droppable: Box<ArcInner<T>>) {
+            DROPPED.fetch_add(1, SeqCst);
+            drop(droppable)
+        }
+    }
+
+    type Node = HeaderSlice<HeaderWithLength<u32>, [String]>;
+
+    offset_arc_ffi! {
+        const NODE_HEADER: OffsetArc<Node, CountingStrategy> as "Node" {
+            retain: test_node_retain,
+            release: test_node_release,
+            count: test_node_count,
+        }
+    }
+
+    #[test]
+    fn retain_and_release() {
+        let node: OffsetArc<Node, CountingStrategy> =
+            OffsetArc::from_header_and_iter(7, (0..3).map(|_| String::from("child")));
+        let ptr = OffsetArc::into_raw(node);
+
+        unsafe {
+            test_node_retain(ptr);
+            assert_eq!(test_node_count(ptr), 2);
+            test_node_release(ptr);
+            assert_eq!(test_node_count(ptr), 1);
+
+            // C can read the header directly
+            assert_eq!(*(ptr as *const u32), 7);
+
+            let node = OffsetArc::<Node, CountingStrategy>::from_raw(ptr);
+            assert_eq!(node.slice.len(), 3);
+            let arc = Arc::from_raw_offset(node);
+            let ptr = OffsetArc::into_raw(Arc::into_raw_offset(arc));
+
+            assert_eq!(DROPPED.load(SeqCst), 0);
+            test_node_release(ptr);
+            assert_eq!(DROPPED.load(SeqCst), 1);
+
+            test_node_retain(ptr::null());
+            test_node_release(ptr::null());
+            assert_eq!(test_node_count(ptr::null()), 0);
+        }
+    }
+
+    #[test]
+    fn generated_header() {
+        assert_eq!(
+            NODE_HEADER,
+            "typedef struct Node Node;\n\
+             void test_node_retain(const Node *ptr);\n\
+             void test_node_release(const Node *ptr);\n\
+             size_t test_node_count(const Node *ptr);\n"
+        );
+        let header = c_header("NODE_H", &[NODE_HEADER]);
+        assert!(header.starts_with("#ifndef NODE_H\n#define NODE_H\n"));
+        assert!(header.contains(NODE_HEADER));
+        assert!(header.ends_with("#endif\n"));
+    }
+}
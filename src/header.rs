@@ -1,16 +1,15 @@
 use alloc::alloc::Layout;
-use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec::Vec;
+use allocator_api2::boxed::Box;
 use core::iter::{ExactSizeIterator, Iterator};
-use core::marker::PhantomData;
 use core::mem::{self, ManuallyDrop};
-use core::ptr::{self, addr_of_mut};
-use core::usize;
+use core::ptr::{self, addr_of_mut, NonNull};
 extern crate backdrop;
 use self::backdrop::BackdropStrategy;
 
-use super::{Arc, ArcInner};
+use super::{AllocError, Arc, ArcInner};
+use allocator_api2::alloc::{Allocator, Global};
 
 /// Structure to allow Arc-managing some fixed-sized data and a variably-sized
 /// slice in a single allocation.
@@ -30,50 +29,77 @@ where
 {
     /// Creates an Arc for a HeaderSlice using the given header struct and
     /// iterator to generate the slice. The resulting Arc will be fat.
-    pub fn from_header_and_iter<I>(header: H, mut items: I) -> Self
+    ///
+    /// If the iterator turns out to report the wrong length,
+    /// this falls back to collecting the items into a `Vec` first.
+    pub fn from_header_and_iter<I>(header: H, items: I) -> Self
     where
         I: Iterator<Item = T> + ExactSizeIterator,
     {
-        assert_ne!(mem::size_of::<T>(), 0, "Need to think about ZST");
+        let inner = Arc::<_, S>::allocate_for_header_and_slice(items.len(), &Global);
 
-        let num_items = items.len();
+        // Safety: `inner` was just allocated for `items.len()` elements
+        unsafe { Self::write_header_and_iter(inner, header, items) }
+            .unwrap_or_else(|(header, items)| Self::from_header_and_vec(header, items))
+    }
 
-        let inner = Arc::<_, S>::allocate_for_header_and_slice(num_items);
+    /// Creates an Arc for a HeaderSlice using the given header struct and
+    /// iterator to generate the slice, returning an error if allocation fails.
+    ///
+    /// ```
+    /// use backdrop_arc::{Arc, HeaderSlice, TrivialStrategy};
+    ///
+    /// let arc: Arc<HeaderSlice<&str, [u32]>, TrivialStrategy> =
+    ///     Arc::try_from_header_and_iter("header", vec![1, 2, 3].into_iter()).unwrap();
+    /// assert_eq!(arc.slice, [1, 2, 3]);
+    /// ```
+    pub fn try_from_header_and_iter<I>(header: H, items: I) -> Result<Self, AllocError>
+    where
+        I: Iterator<Item = T> + ExactSizeIterator,
+    {
+        let inner = Arc::<_, S>::try_allocate_for_header_and_slice(items.len(), &Global)?;
 
-        unsafe {
-            // Write the data.
-            //
-            // Note that any panics here (i.e. from the iterator) are safe, since
-            // we'll just leak the uninitialized memory.
-            ptr::write(&mut ((*inner.as_ptr()).data.header), header);
-            if num_items != 0 {
-                let mut current = (*inner.as_ptr()).data.slice.as_mut_ptr();
-                for _ in 0..num_items {
-                    ptr::write(
-                        current,
-                        items
-                            .next()
-                            .expect("ExactSizeIterator over-reported length"),
-                    );
-                    current = current.offset(1);
-                }
-                assert!(
-                    items.next().is_none(),
-                    "ExactSizeIterator under-reported length"
-                );
-            }
-            assert!(
-                items.next().is_none(),
-                "ExactSizeIterator under-reported length"
-            );
+        // Safety: `inner` was just allocated for `items.len()` elements
+        match unsafe { Self::write_header_and_iter(inner, header, items) } {
+            Ok(arc) => Ok(arc),
+            Err((header, items)) => Self::try_from_header_and_vec(header, items),
         }
+    }
+
+    /// Writes the header and items into `inner`.
+    ///
+    /// If `items` does not yield exactly `items.len()` items,
+    /// `inner` is freed and the header and all items are handed back instead.
+    ///
+    /// # Safety
+    ///
+    /// `inner` must be a freshly allocated (and otherwise uninitialized) `ArcInner`
+    /// whose slice has room for exactly `items.len()` elements.
+    unsafe fn write_header_and_iter<I>(
+        inner: NonNull<ArcInner<HeaderSlice<H, [T]>>>,
+        header: H,
+        items: I,
+    ) -> Result<Self, (H, Vec<T>)>
+    where
+        I: Iterator<Item = T> + ExactSizeIterator,
+    {
+        let num_items = items.len();
+        let layout = Arc::<_, S>::inner_layout_for_header_and_slice(num_items)
+            .expect("layout was allocated before");
+
+        // Safety: `inner` is valid, and allocated with `layout`
+        PartialHeaderSlice::write(
+            inner.cast(),
+            layout,
+            addr_of_mut!((*inner.as_ptr()).data.header),
+            addr_of_mut!((*inner.as_ptr()).data.slice) as *mut T,
+            header,
+            num_items,
+            items,
+        )?;
 
         // Safety: ptr is valid & the inner structure is fully initialized
-        Arc {
-            p: inner,
-            phantom: PhantomData,
-            phantom_strategy: PhantomData,
-        }
+        Ok(Arc::from_raw_inner(inner.as_ptr()))
     }
 
     /// Creates an Arc for a HeaderSlice using the given header struct and
@@ -82,66 +108,100 @@ where
     where
         T: Copy,
     {
-        assert_ne!(mem::size_of::<T>(), 0, "Need to think about ZST");
+        let inner = Arc::<_, S>::allocate_for_header_and_slice(items.len(), &Global);
 
-        let num_items = items.len();
+        // Safety: `inner` was just allocated for `items.len()` elements
+        unsafe { Self::write_header_and_slice(inner, header, items) }
+    }
 
-        let inner = Arc::<_, S>::allocate_for_header_and_slice(num_items);
+    /// Creates an Arc for a HeaderSlice using the given header struct and
+    /// a slice to copy, returning an error if allocation fails.
+    pub fn try_from_header_and_slice(header: H, items: &[T]) -> Result<Self, AllocError>
+    where
+        T: Copy,
+    {
+        let inner = Arc::<_, S>::try_allocate_for_header_and_slice(items.len(), &Global)?;
 
-        unsafe {
-            // Write the data.
-            ptr::write(&mut ((*inner.as_ptr()).data.header), header);
-            let dst = (*inner.as_ptr()).data.slice.as_mut_ptr();
-            ptr::copy_nonoverlapping(items.as_ptr(), dst, num_items);
-        }
+        // Safety: `inner` was just allocated for `items.len()` elements
+        Ok(unsafe { Self::write_header_and_slice(inner, header, items) })
+    }
+
+    /// # Safety
+    ///
+    /// `inner` must be a freshly allocated (and otherwise uninitialized) `ArcInner`
+    /// whose slice has room for exactly `items.len()` elements.
+    unsafe fn write_header_and_slice(
+        inner: NonNull<ArcInner<HeaderSlice<H, [T]>>>,
+        header: H,
+        items: &[T],
+    ) -> Self
+    where
+        T: Copy,
+    {
+        // Write the data.
+        ptr::write(&mut ((*inner.as_ptr()).data.header), header);
+        let dst = (*inner.as_ptr()).data.slice.as_mut_ptr();
+        ptr::copy_nonoverlapping(items.as_ptr(), dst, items.len());
 
         // Safety: ptr is valid & the inner structure is fully initialized
-        Arc {
-            p: inner,
-            phantom: PhantomData,
-            phantom_strategy: PhantomData,
-        }
+        Arc::from_raw_inner(inner.as_ptr())
     }
 
     /// Creates an Arc for a HeaderSlice using the given header struct and
     /// vec to generate the slice. The resulting Arc will be fat.
-    pub fn from_header_and_vec(header: H, mut v: Vec<T>) -> Self {
-        let len = v.len();
+    pub fn from_header_and_vec(header: H, v: Vec<T>) -> Self {
+        let inner = Arc::<_, S>::allocate_for_header_and_slice(v.len(), &Global);
 
-        let inner = Arc::<_, S>::allocate_for_header_and_slice(len);
+        // Safety: `inner` was just allocated for `v.len()` elements
+        unsafe { Self::write_header_and_vec(inner, header, v) }
+    }
 
-        unsafe {
-            // Safety: inner is a valid pointer, so this can't go out of bounds
-            let dst = addr_of_mut!((*inner.as_ptr()).data.header);
+    /// Creates an Arc for a HeaderSlice using the given header struct and
+    /// vec to generate the slice, returning an error if allocation fails.
+    ///
+    /// On failure, `header` and `v` are dropped.
+    pub fn try_from_header_and_vec(header: H, v: Vec<T>) -> Result<Self, AllocError> {
+        let inner = Arc::<_, S>::try_allocate_for_header_and_slice(v.len(), &Global)?;
+
+        // Safety: `inner` was just allocated for `v.len()` elements
+        Ok(unsafe { Self::write_header_and_vec(inner, header, v) })
+    }
 
-            // Safety: `dst` is valid for writes (just allocated)
-            ptr::write(dst, header);
-        }
+    /// # Safety
+    ///
+    /// `inner` must be a freshly allocated (and otherwise uninitialized) `ArcInner`
+    /// whose slice has room for exactly `v.len()` elements.
+    unsafe fn write_header_and_vec(
+        inner: NonNull<ArcInner<HeaderSlice<H, [T]>>>,
+        header: H,
+        mut v: Vec<T>,
+    ) -> Self {
+        let len = v.len();
 
-        unsafe {
-            let src = v.as_mut_ptr();
+        // Safety: inner is a valid pointer, so this can't go out of bounds
+        let dst = addr_of_mut!((*inner.as_ptr()).data.header);
 
-            // Safety: inner is a valid pointer, so this can't go out of bounds
-            let dst = addr_of_mut!((*inner.as_ptr()).data.slice) as *mut T;
+        // Safety: `dst` is valid for writes (just allocated)
+        ptr::write(dst, header);
 
-            // Safety:
-            // - `src` is valid for reads for `len` (got from `Vec`)
-            // - `dst` is valid for writes for `len` (just allocated, with layout for appropriate slice)
-            // - `src` and `dst` don't overlap (separate allocations)
-            ptr::copy_nonoverlapping(src, dst, len);
+        let src = v.as_mut_ptr();
 
-            // Deallocate vec without dropping `T`
-            //
-            // Safety: 0..0 elements are always initialized, 0 <= cap for any cap
-            v.set_len(0);
-        }
+        // Safety: inner is a valid pointer, so this can't go out of bounds
+        let dst = addr_of_mut!((*inner.as_ptr()).data.slice) as *mut T;
+
+        // Safety:
+        // - `src` is valid for reads for `len` (got from `Vec`)
+        // - `dst` is valid for writes for `len` (just allocated, with layout for appropriate slice)
+        // - `src` and `dst` don't overlap (separate allocations)
+        ptr::copy_nonoverlapping(src, dst, len);
+
+        // Deallocate vec without dropping `T`
+        //
+        // Safety: 0..0 elements are always initialized, 0 <= cap for any cap
+        v.set_len(0);
 
         // Safety: ptr is valid & the inner structure is fully initialized
-        Arc {
-            p: inner,
-            phantom: PhantomData,
-            phantom_strategy: PhantomData,
-        }
+        Arc::from_raw_inner(inner.as_ptr())
     }
 }
 
@@ -240,47 +300,77 @@ where
     }
 }
 
-// FIXME: once `pointer::with_metadata_of` is stable or
-//        implementable on stable without assuming ptr layout
-//        this will be able to accept `T: ?Sized`.
-impl<T, S> From<Box<T>> for Arc<T, S>
+/// Moves a boxed (possibly unsized) value into a new `Arc`.
+///
+/// This works for trait objects, `str` and slices alike:
+/// the value's bytes are copied into a fresh `ArcInner`, keeping the pointer metadata
+/// (vtable or length) of the box, after which the box's allocation is freed
+/// without running the value's destructor.
+///
+/// ```
+/// use backdrop_arc::{Arc, TrivialStrategy};
+/// use std::fmt::Display;
+///
+/// let boxed: Box<dyn Display + Send + Sync> = Box::new(42);
+/// let arc: Arc<dyn Display + Send + Sync, TrivialStrategy> = Arc::from(boxed);
+/// assert_eq!(arc.to_string(), "42");
+///
+/// let arc: Arc<str, TrivialStrategy> = Arc::from(Box::<str>::from("hello"));
+/// assert_eq!(&*arc, "hello");
+/// ```
+impl<T: ?Sized, S> From<alloc::boxed::Box<T>> for Arc<T, S>
 where
     S: BackdropStrategy<Box<ArcInner<T>>>,
 {
-    fn from(b: Box<T>) -> Self {
+    fn from(b: alloc::boxed::Box<T>) -> Self {
         let layout = Layout::for_value::<T>(&b);
+        let src = alloc::boxed::Box::into_raw(b);
 
-        // Safety: the closure only changes the type of the pointer
-        let inner = unsafe { Self::allocate_for_layout(layout, |mem| mem as *mut ArcInner<T>) };
+        // Safety: the closure only changes the address of the pointer, keeping the metadata of `src`
+        let inner = unsafe {
+            Self::allocate_for_layout(layout, &Global, |mem| {
+                set_data_ptr(src as *mut ArcInner<T>, mem)
+            })
+        };
 
         unsafe {
-            let src = Box::into_raw(b);
-
             // Safety: inner is a valid pointer, so this can't go out of bounds
-            let dst = addr_of_mut!((*inner.as_ptr()).data);
+            let dst = addr_of_mut!((*inner.as_ptr()).data) as *mut u8;
 
             // Safety:
-            // - `src` is valid for reads (got from `Box`)
-            // - `dst` is valid for writes (just allocated)
+            // - `src` is valid for reads of `layout.size()` bytes (got from `Box`)
+            // - `dst` is valid for writes of `layout.size()` bytes (just allocated for `layout`)
             // - `src` and `dst` don't overlap (separate allocations)
-            ptr::copy_nonoverlapping(src, dst, 1);
+            ptr::copy_nonoverlapping(src as *const u8, dst, layout.size());
 
             // Deallocate box without dropping `T`
             //
             // Safety:
-            // - `src` has been got from `Box::into_raw`
-            // - `ManuallyDrop<T>` is guaranteed to have the same layout as `T`
-            drop(Box::<ManuallyDrop<T>>::from_raw(src as _));
+            // - `src` has been got from `Box::into_raw`, which allocated it with `layout`
+            // - zero-sized boxes do not own an allocation
+            if layout.size() != 0 {
+                alloc::alloc::dealloc(src as *mut u8, layout);
+            }
         }
 
-        Arc {
-            p: inner,
-            phantom: PhantomData,
-            phantom_strategy: PhantomData,
-        }
+        // Safety: ptr is valid & the inner structure is fully initialized
+        unsafe { Arc::from_raw_inner(inner.as_ptr()) }
     }
 }
 
+/// Replaces the address of a (possibly fat) pointer, keeping its metadata.
+///
+/// FIXME: use `pointer::with_metadata_of` once it is stable.
+///
+/// # Safety
+///
+/// Assumes the address is stored in the first word of a fat pointer,
+/// which holds for all pointer kinds the compiler currently produces.
+pub(crate) unsafe fn set_data_ptr<T: ?Sized>(mut ptr: *mut T, data: *mut u8) -> *mut T {
+    ptr::write(&mut ptr as *mut *mut T as *mut *mut u8, data);
+    ptr
+}
+
 impl<T, S> From<Vec<T>> for Arc<[T], S>
 where
     S: BackdropStrategy<Box<ArcInner<[T]>>>,
@@ -291,17 +381,143 @@ where
     }
 }
 
-// pub(crate) type HeaderSliceWithLength<H, T> = HeaderSlice<HeaderWithLength<H>, T>;
+pub(crate) type HeaderSliceWithLength<H, T> = HeaderSlice<HeaderWithLength<H>, T>;
+
+impl<H, T, S> Arc<HeaderSliceWithLength<H, [T]>, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
+    /// Like `from_header_and_iter`, but stores the number of items in the header.
+    ///
+    /// This stays correct when the iterator reports the wrong length.
+    pub(crate) fn from_header_with_length_and_iter<I>(header: H, items: I) -> Self
+    where
+        I: Iterator<Item = T> + ExactSizeIterator,
+    {
+        let header = HeaderWithLength::new(header, items.len());
+        let inner = Arc::<_, S>::allocate_for_header_and_slice(items.len(), &Global);
+
+        // Safety: `inner` was just allocated for `items.len()` elements
+        unsafe { Self::write_header_and_iter(inner, header, items) }.unwrap_or_else(
+            |(header, items)| {
+                let header = HeaderWithLength::new(header.header, items.len());
+                Self::from_header_and_vec(header, items)
+            },
+        )
+    }
+}
+
+/// The header and the first `len` elements of a `HeaderSlice`
+/// which is being written into a fresh `ArcInner` or `RcInner`.
+///
+/// If this is dropped (i.e. because the iterator producing the elements panicked),
+/// the header and elements written so far are dropped and the allocation is freed.
+pub(crate) struct PartialHeaderSlice<H, T> {
+    mem: NonNull<u8>,
+    layout: Layout,
+    header: *mut H,
+    slice: *mut T,
+    len: usize,
+}
+
+impl<H, T> PartialHeaderSlice<H, T> {
+    /// Writes `header` to `header_ptr`, and `num_items` items from `items` to `slice_ptr`.
+    ///
+    /// If `items` yields fewer or more items, the allocation is freed
+    /// and the header and all items are returned instead.
+    ///
+    /// # Safety
+    ///
+    /// `mem` must be allocated by `Global` using `layout`.
+    /// `header_ptr` and `slice_ptr` must point into it,
+    /// with room for a header and `num_items` elements respectively.
+    pub(crate) unsafe fn write<I>(
+        mem: NonNull<u8>,
+        layout: Layout,
+        header_ptr: *mut H,
+        slice_ptr: *mut T,
+        header: H,
+        num_items: usize,
+        mut items: I,
+    ) -> Result<(), (H, Vec<T>)>
+    where
+        I: Iterator<Item = T>,
+    {
+        ptr::write(header_ptr, header);
+        let mut partial = PartialHeaderSlice {
+            mem,
+            layout,
+            header: header_ptr,
+            slice: slice_ptr,
+            len: 0,
+        };
+
+        while partial.len < num_items {
+            match items.next() {
+                Some(item) => {
+                    ptr::write(partial.slice.add(partial.len), item);
+                    partial.len += 1;
+                }
+                // The iterator over-reported its length
+                None => return Err(partial.into_header_and_vec()),
+            }
+        }
+        match items.next() {
+            None => {
+                mem::forget(partial);
+                Ok(())
+            }
+            // The iterator under-reported its length
+            Some(item) => {
+                let (header, mut vec) = partial.into_header_and_vec();
+                vec.push(item);
+                vec.extend(items);
+                Err((header, vec))
+            }
+        }
+    }
+
+    /// Moves the header and elements written so far out, and frees the allocation.
+    fn into_header_and_vec(self) -> (H, Vec<T>) {
+        // Allocate before taking ownership, so a panic here still cleans up
+        let mut vec = Vec::with_capacity(self.len);
+        let this = ManuallyDrop::new(self);
+
+        // Safety: the header and first `len` elements are initialized,
+        // and are not used again after being moved out
+        unsafe {
+            let header = ptr::read(this.header);
+            ptr::copy_nonoverlapping(this.slice, vec.as_mut_ptr(), this.len);
+            vec.set_len(this.len);
+            Global.deallocate(this.mem, this.layout);
+            (header, vec)
+        }
+    }
+}
+
+impl<H, T> Drop for PartialHeaderSlice<H, T> {
+    fn drop(&mut self) {
+        // Safety: the header and first `len` elements are initialized,
+        // and `mem` was allocated using `layout`
+        unsafe {
+            ptr::drop_in_place(self.header);
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.slice, self.len));
+            Global.deallocate(self.mem, self.layout);
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::backdrop::TrivialStrategy;
     use alloc::boxed::Box;
-    use alloc::string::String;
+    use alloc::string::{String, ToString};
     use alloc::vec;
+    use alloc::vec::Vec;
+    use core::cell::Cell;
     use core::iter;
 
-    use crate::{Arc, HeaderSlice};
+    use crate::{Arc, HeaderSlice, Rc, ThinArc};
 
     #[test]
     fn from_header_and_iter_smoke() {
@@ -336,6 +552,25 @@ mod tests {
         assert_eq!(arc.slice, [1u16, 2, 3, 4, 5, 6, 7]);
     }
 
+    #[test]
+    fn try_from_header_and_iter_smoke() {
+        let arc = Arc::<_, TrivialStrategy>::try_from_header_and_iter(
+            (42u32, 17u8),
+            IntoIterator::into_iter([1u16, 2, 3, 4, 5, 6, 7]),
+        )
+        .unwrap();
+
+        assert_eq!(arc.header, (42, 17));
+        assert_eq!(arc.slice, [1, 2, 3, 4, 5, 6, 7]);
+
+        let arc = Arc::<_, TrivialStrategy>::try_from_header_and_vec(
+            (42u32, 17u8),
+            vec![String::from("1"), String::from("2")],
+        )
+        .unwrap();
+        assert_eq!(arc.slice, [String::from("1"), String::from("2")]);
+    }
+
     #[test]
     fn from_header_and_iter_empty() {
         let arc =
@@ -345,6 +580,81 @@ mod tests {
         assert_eq!(arc.slice, []);
     }
 
+    /// Reports `len`, but yields `count` items
+    struct Lying {
+        len: usize,
+        count: usize,
+    }
+
+    impl Iterator for Lying {
+        type Item = String;
+
+        fn next(&mut self) -> Option<String> {
+            self.count = self.count.checked_sub(1)?;
+            Some(self.count.to_string())
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.len, Some(self.len))
+        }
+    }
+
+    impl ExactSizeIterator for Lying {}
+
+    #[test]
+    fn from_header_and_iter_wrong_length() {
+        let arc = Arc::<_, TrivialStrategy>::from_header_and_iter(1, Lying { len: 5, count: 3 });
+        assert_eq!(arc.slice, ["2", "1", "0"]);
+        let arc =
+            Arc::<_, TrivialStrategy>::try_from_header_and_iter(1, Lying { len: 2, count: 4 })
+                .unwrap();
+        assert_eq!(arc.slice, ["3", "2", "1", "0"]);
+
+        let thin =
+            ThinArc::<_, _, TrivialStrategy>::from_header_and_iter(1, Lying { len: 1, count: 2 });
+        assert_eq!(thin.header.length, 2);
+        assert_eq!(thin.slice, ["1", "0"]);
+
+        let rc = Rc::<_, TrivialStrategy>::from_header_and_iter(1, Lying { len: 3, count: 0 });
+        assert!(rc.slice.is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn from_header_and_iter_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        struct DropCounter<'a>(&'a Cell<usize>);
+        impl<'a> Drop for DropCounter<'a> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        let items = (0..5).map(|i| {
+            assert!(i < 3, "iterator panicked");
+            DropCounter(&drops)
+        });
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            Arc::<_, TrivialStrategy>::from_header_and_iter(DropCounter(&drops), items)
+        }));
+        assert!(result.is_err());
+        // The header and the three elements written before the panic
+        assert_eq!(drops.get(), 4);
+
+        drops.set(0);
+        let items = (0..5).map(|i| {
+            assert!(i < 2, "iterator panicked");
+            DropCounter(&drops)
+        });
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            Rc::<_, TrivialStrategy>::from_header_and_iter(DropCounter(&drops), items)
+        }));
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 3);
+    }
+
     #[test]
     fn from_header_and_slice_empty() {
         let arc = Arc::<_, TrivialStrategy>::from_header_and_slice((42u32, 17u8), &[1u16; 0]);
@@ -372,6 +682,64 @@ mod tests {
         crate::Arc::<_, TrivialStrategy>::from_header_and_iter((), s.iter().copied());
     }
 
+    #[test]
+    fn zst_elements() {
+        use core::iter::FromIterator;
+        use core::mem::MaybeUninit;
+        use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+        use crate::UniqueArc;
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, PartialEq)]
+        struct Marker;
+        impl Drop for Marker {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, SeqCst);
+            }
+        }
+
+        let arc: Arc<HeaderSlice<u8, [Marker]>, TrivialStrategy> =
+            Arc::from_header_and_iter(7, (0..5).map(|_| Marker));
+        assert_eq!(arc.header, 7);
+        assert_eq!(arc.slice.len(), 5);
+        drop(arc);
+        assert_eq!(DROPS.load(SeqCst), 5);
+
+        let arc: Arc<HeaderSlice<u8, [Marker]>, TrivialStrategy> =
+            Arc::from_header_and_vec(7, (0..3).map(|_| Marker).collect());
+        assert_eq!(arc.slice.len(), 3);
+        drop(arc);
+        assert_eq!(DROPS.load(SeqCst), 8);
+
+        let arc: Arc<HeaderSlice<u8, [()]>, TrivialStrategy> =
+            Arc::try_from_header_and_slice(7, &[(); 4]).unwrap();
+        assert_eq!(arc.slice.len(), 4);
+
+        // `FromIterator`, with and without an exact size hint
+        let arc = Arc::<[()], TrivialStrategy>::from_iter((0..3).map(|_| ()));
+        assert_eq!(arc.len(), 3);
+        let arc =
+            Arc::<[()], TrivialStrategy>::from_iter((0..10).filter(|x| x % 2 == 0).map(|_| ()));
+        assert_eq!(arc.len(), 5);
+        let arc = Arc::<[()], TrivialStrategy>::from(vec![(); 2]);
+        assert_eq!(arc.len(), 2);
+
+        let mut uninit = UniqueArc::<[MaybeUninit<()>], TrivialStrategy>::new_uninit_slice(6);
+        for elem in uninit.iter_mut() {
+            elem.write(());
+        }
+        let arc = unsafe { UniqueArc::assume_init_slice(uninit) }.shareable();
+        assert_eq!(arc.len(), 6);
+
+        let rc: Rc<HeaderSlice<u8, [()]>, TrivialStrategy> =
+            Rc::from_header_and_iter(1, (0..2).map(|_| ()));
+        assert_eq!(rc.slice.len(), 2);
+        let rc: Rc<HeaderSlice<u8, [()]>, TrivialStrategy> = Rc::from_header_and_slice(1, &[(); 3]);
+        assert_eq!(rc.slice.len(), 3);
+    }
+
     #[test]
     fn from_header_and_str_smoke() {
         let a = Arc::<_, TrivialStrategy>::from_header_and_str(
@@ -424,4 +792,49 @@ mod tests {
             [String::from("1"), String::from("2"), String::from("3")]
         );
     }
+
+    #[test]
+    fn from_box_unsized() {
+        struct DropCounter<'a>(&'a Cell<usize>);
+        impl<'a> Drop for DropCounter<'a> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+        trait Named {
+            fn name(&self) -> String;
+        }
+        impl<'a> Named for DropCounter<'a> {
+            fn name(&self) -> String {
+                String::from("counter")
+            }
+        }
+        impl Named for () {
+            fn name(&self) -> String {
+                String::from("unit")
+            }
+        }
+
+        let drops = Cell::new(0);
+        let b: Box<dyn Named + '_> = Box::new(DropCounter(&drops));
+        let arc = Arc::<dyn Named, TrivialStrategy>::from(b);
+        assert_eq!(drops.get(), 0);
+        assert_eq!(arc.name(), "counter");
+        drop(arc);
+        assert_eq!(drops.get(), 1);
+
+        let b: Box<dyn Named> = Box::new(());
+        let arc = Arc::<dyn Named, TrivialStrategy>::from(b);
+        assert_eq!(arc.name(), "unit");
+
+        let s = Arc::<str, TrivialStrategy>::from(Box::<str>::from("boxed str"));
+        assert_eq!(&*s, "boxed str");
+
+        let b: Box<[String]> = vec![String::from("1"), String::from("2")].into_boxed_slice();
+        let v = Arc::<[String], TrivialStrategy>::from(b);
+        assert_eq!(&*v, [String::from("1"), String::from("2")]);
+
+        let empty = Arc::<[u64], TrivialStrategy>::from(Vec::new().into_boxed_slice());
+        assert!(empty.is_empty());
+    }
 }
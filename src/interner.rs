@@ -0,0 +1,326 @@
+use alloc::vec::Vec;
+use allocator_api2::boxed::Box;
+use core::fmt;
+use core::hash::Hash;
+use std::collections::HashSet;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+use super::{Arc, ArcInner};
+
+/// The minimum number of `intern*` calls between two automatic prunes.
+const MIN_PRUNE_INTERVAL: usize = 32;
+
+/// A pool of deduplicated `Arc<T, S>`s.
+///
+/// Interning a value returns an `Arc` which is shared with all other
+/// (earlier or later) interned values that are equal to it.
+/// `T` may be unsized, e.g. `str` or `[T]`.
+///
+/// The interner holds on to one clone of each `Arc`,
+/// so a value is not dropped as soon as all other clones are.
+/// Instead, the interner periodically prunes values no longer used outside of the pool
+/// (dropping them using the strategy `S`): once every so many `intern*` calls,
+/// as many as the pool held values after the previous prune (but at least 32).
+/// This keeps the cost of pruning amortized O(1) per call, whether or not the pool is still growing.
+///
+/// A pool which is not used anymore does not prune itself,
+/// so call [`prune`](ArcInterner::prune) to release its unused values right away.
+///
+/// ```
+/// use backdrop_arc::{Arc, ArcInterner, TrivialStrategy};
+///
+/// let interner: ArcInterner<str, TrivialStrategy> = ArcInterner::new();
+/// let a = interner.intern("identifier");
+/// let b = interner.intern(&String::from("identifier"));
+/// assert!(Arc::ptr_eq(&a, &b));
+/// assert_eq!(interner.len(), 1);
+///
+/// drop(a);
+/// drop(b);
+/// assert_eq!(interner.prune(), 1);
+/// assert!(interner.is_empty());
+/// ```
+pub struct ArcInterner<T: ?Sized, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    pool: Mutex<Pool<T, S>>,
+}
+
+struct Pool<T: ?Sized, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    arcs: HashSet<Arc<T, S>>,
+    /// The number of `intern*` calls left until the next automatic prune.
+    until_prune: usize,
+}
+
+impl<T: ?Sized + Hash + Eq, S> ArcInterner<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    /// Creates an empty interner.
+    pub fn new() -> Self {
+        ArcInterner {
+            pool: Mutex::new(Pool {
+                arcs: HashSet::new(),
+                until_prune: MIN_PRUNE_INTERVAL,
+            }),
+        }
+    }
+
+    /// Returns the interned `Arc` equal to `value`,
+    /// or creates (and interns) a new one by converting `value` into an `Arc`.
+    #[inline]
+    pub fn intern(&self, value: &T) -> Arc<T, S>
+    where
+        for<'a> Arc<T, S>: From<&'a T>,
+    {
+        self.intern_with(value, |value| Arc::from(value))
+    }
+
+    /// Returns the interned `Arc` equal to `value`,
+    /// or creates (and interns) a new one using `make_arc`.
+    ///
+    /// This allows interning values which can't be converted from a reference directly,
+    /// e.g. using the `HeaderSlice` constructors.
+    ///
+    /// `make_arc` must return an `Arc` which is equal to `value`.
+    pub fn intern_with<F>(&self, value: &T, make_arc: F) -> Arc<T, S>
+    where
+        F: FnOnce(&T) -> Arc<T, S>,
+    {
+        if let Some(arc) = self.get(value) {
+            return arc;
+        }
+        // Create the `Arc` without holding the lock;
+        // if another thread interned an equal value meanwhile, `insert` returns that one instead.
+        self.insert(make_arc(value))
+    }
+
+    /// Returns the interned `Arc` equal to `value`, or interns `value` in a new `Arc`.
+    pub fn intern_value(&self, value: T) -> Arc<T, S>
+    where
+        T: Sized,
+    {
+        if let Some(arc) = self.get(&value) {
+            return arc;
+        }
+        self.insert(Arc::new(value))
+    }
+
+    /// Returns the interned `Arc` equal to `arc`, or interns `arc` itself.
+    pub fn intern_arc(&self, arc: Arc<T, S>) -> Arc<T, S> {
+        self.insert(arc)
+    }
+
+    /// Whether `arc` is (a clone of) the `Arc` interned in this pool.
+    ///
+    /// This is false for an `Arc` that is merely equal to an interned one.
+    pub fn contains(&self, arc: &Arc<T, S>) -> bool {
+        match self.lock().arcs.get(&**arc) {
+            Some(interned) => Arc::ptr_eq(interned, arc),
+            None => false,
+        }
+    }
+
+    /// The number of values in the pool, including any that are no longer used elsewhere
+    /// but were not pruned yet.
+    pub fn len(&self) -> usize {
+        self.lock().arcs.len()
+    }
+
+    /// Whether the pool is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().arcs.is_empty()
+    }
+
+    /// Removes all values which are no longer used outside of the pool,
+    /// returning how many were removed.
+    ///
+    /// The values are dropped using the strategy `S` (after the pool is unlocked again).
+    pub fn prune(&self) -> usize {
+        let removed = self.lock().prune();
+        let count = removed.len();
+        drop(removed);
+        count
+    }
+
+    /// Returns the interned `Arc` equal to `value`, if any.
+    fn get(&self, value: &T) -> Option<Arc<T, S>> {
+        let mut pool = self.lock();
+        let arc = pool.arcs.get(value).cloned()?;
+        let removed = pool.tick();
+        drop(pool);
+
+        // Only drop the removed values once the pool is unlocked again
+        drop(removed);
+        Some(arc)
+    }
+
+    fn insert(&self, arc: Arc<T, S>) -> Arc<T, S> {
+        let mut pool = self.lock();
+        let interned = match pool.arcs.get(&*arc) {
+            Some(interned) => interned.clone(),
+            None => {
+                pool.arcs.insert(arc.clone());
+                arc
+            }
+        };
+        let removed = pool.tick();
+        drop(pool);
+
+        drop(removed);
+        interned
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Pool<T, S>> {
+        // The pool is never left in an inconsistent state, even on panic
+        self.pool.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: ?Sized + Hash + Eq, S> Pool<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    /// Counts an `intern*` call, pruning if enough calls were made since the previous prune.
+    ///
+    /// Must be called after cloning the `Arc` returned by the call,
+    /// so that it isn't pruned.
+    fn tick(&mut self) -> Vec<Arc<T, S>> {
+        self.until_prune = self.until_prune.saturating_sub(1);
+        if self.until_prune > 0 {
+            return Vec::new();
+        }
+        let removed = self.prune();
+        self.until_prune = self.arcs.len().max(MIN_PRUNE_INTERVAL);
+        removed
+    }
+
+    fn prune(&mut self) -> Vec<Arc<T, S>> {
+        // While the pool is locked, an `Arc` whose only clone is the interned one
+        // can't be cloned again, so it can be removed without racing other threads.
+        let mut removed = Vec::new();
+        self.arcs.retain(|arc| {
+            if Arc::count(arc) == 1 {
+                removed.push(arc.clone());
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+}
+
+impl<T: ?Sized + Hash + Eq, S> Default for ArcInterner<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized, S> fmt::Debug for ArcInterner<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let len = match self.pool.try_lock() {
+            Ok(pool) => Some(pool.arcs.len()),
+            Err(_) => None,
+        };
+        f.debug_struct("ArcInterner").field("len", &len).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::{BackdropStrategy, TrivialStrategy};
+    use super::ArcInterner;
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+    use allocator_api2::boxed::Box;
+    use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    use crate::{Arc, ArcInner, HeaderSlice};
+
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingStrategy;
+
+    impl<T: ?Sized> BackdropStrategy<Box<ArcInner<T>>> for CountingStrategy {
+        fn execute(droppable: Box<ArcInner<T>>) {
+            DROPPED.fetch_add(1, SeqCst);
+            drop(droppable)
+        }
+    }
+
+    #[test]
+    fn interns_and_prunes() {
+        let interner: ArcInterner<str, CountingStrategy> = ArcInterner::new();
+        let a = interner.intern("a");
+        let b = interner.intern("b");
+        let other_a: Arc<str, CountingStrategy> = Arc::from("a");
+        assert!(Arc::ptr_eq(&a, &interner.intern("a")));
+        assert!(Arc::ptr_eq(&a, &interner.intern_arc(other_a.clone())));
+        assert!(interner.contains(&a));
+        assert!(!interner.contains(&other_a));
+        assert_eq!(interner.len(), 2);
+
+        drop(a);
+        assert_eq!(interner.prune(), 1);
+        assert_eq!(DROPPED.load(SeqCst), 1);
+        assert_eq!(interner.len(), 1);
+        assert_eq!(&*b, "b");
+
+        // Values no longer used are removed automatically as the pool grows
+        for i in 0..1000 {
+            interner.intern(&i.to_string());
+        }
+        assert!(interner.len() < 100);
+        drop(b);
+        drop(interner);
+        assert_eq!(DROPPED.load(SeqCst), 1002);
+    }
+
+    #[test]
+    fn prunes_without_growing() {
+        let interner: ArcInterner<str, TrivialStrategy> = ArcInterner::new();
+        let a = interner.intern("a");
+        let b = interner.intern("b");
+        drop(a);
+        // Only values that are interned already
+        for _ in 0..100 {
+            assert!(Arc::ptr_eq(&b, &interner.intern("b")));
+        }
+        assert_eq!(interner.len(), 1);
+        assert!(interner.contains(&b));
+    }
+
+    #[test]
+    fn interns_slices() {
+        let interner: ArcInterner<[String], TrivialStrategy> = ArcInterner::default();
+        let items = ["x".to_string(), "y".to_string()];
+        let a = interner.intern_with(&items, |items| items.iter().cloned().collect());
+        let b = interner.intern_with(&items, |_| unreachable!());
+        assert!(Arc::ptr_eq(&a, &b));
+
+        let interner: ArcInterner<HeaderSlice<u8, [u8]>, TrivialStrategy> = ArcInterner::new();
+        let value: Arc<HeaderSlice<u8, [u8]>, TrivialStrategy> =
+            Arc::from_header_and_slice(1, &[2, 3]);
+        let a = interner.intern_with(&value, |v| Arc::from_header_and_slice(v.header, &v.slice));
+        assert!(!Arc::ptr_eq(&a, &value));
+        assert!(Arc::ptr_eq(&a, &interner.intern_arc(value)));
+
+        let interner: ArcInterner<Vec<u8>, TrivialStrategy> = ArcInterner::new();
+        let a = interner.intern_value(vec![1, 2]);
+        assert!(Arc::ptr_eq(&a, &interner.intern_value(vec![1, 2])));
+    }
+}
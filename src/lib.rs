@@ -40,15 +40,29 @@
 //! - [`OffsetArc`] can be used transparently from C++ code and is compatible with (and can be converted to/from) [`Arc`].
 //! - [`ArcBorrow`] is functionally similar to `&backdrop_arc::Arc<T>`, however in memory it's simply `&T`. This makes it more flexible for FFI; the source of the borrow need not be an Arc pinned on the stack (and can instead be a pointer from C++, or an `OffsetArc`). Additionally, this helps avoid pointer-chasing.
 //! - [`Arc`] has can be constructed for dynamically-sized types via `from_header_and_iter`
+//! - [`ArcSliceBuilder`] grows a `HeaderSlice` of unknown length directly inside the allocation of the [`Arc`] it will become
+//! - [`ArcStrBuilder`] and [`arc_format!`] do the same for strings
+//! - [`ArcInterner`] deduplicates equal values into shared [`Arc`]s, periodically pruning the ones no longer used (requires the `std` feature)
+//! - [`ThinArc`] provides thin-pointer `Arc`s to dynamically sized types
 //! - [`ArcUnion`] is union of two [`Arc`]s which fits inside one word of memory
+//! - [`ArcUnion4`] is the same for four [`Arc`]s
+//! - [`ArcSlice`] is a zero-copy view into a sub-slice of an `Arc<[T]>`, sharing its refcount
+//! - [`MappedArc`] is an owning projection into (a field of) an [`Arc`], created with [`Arc::map`]
+//! - [`Arc`] and [`UniqueArc`] can be allocated in a custom [`Allocator`] (using the stable [`allocator_api2`](https://crates.io/crates/allocator-api2) polyfill) with [`Arc::new_in`].
+//! - [`DynStrategy`] stores the drop strategy per allocation, so it can be chosen at runtime with [`Arc::new_with_strategy`].
+//! - [`Rc`], [`UniqueRc`] and [`RcBorrow`] are non-atomic (single-threaded) counterparts of [`Arc`], [`UniqueArc`] and [`ArcBorrow`], with the same strategy support.
+//! - [`ThresholdStrategy`] only moves drops to the background when their (estimated) [`DropCost`] is high.
 //! # Features
 //!
 //! - `backdrop_arc` supports no_std environments, as long as `alloc` is available, by disabling the (enabled by default) `std` feature.
 //! - `serde`: Enables serialization/deserialization with the [`serde`](https://crates.io/crates/serde) crate.
+//!   Together with `std`, the [`serde_shared`] module (de)serializes [`Arc`]s while preserving which of them share an allocation.
 //! - `stable_deref_trait`: Implements the `StableDeref` trait from the [`stable_deref_trait`](https://crates.io/crates/stable_deref_trait) crate for [`Arc`].
 //! - `arc-swap`: Use [`Arc`] together with the [`arc-swap`](https://crates.io/crates/arc-swap) crate.
 //! - `triomphe`: Convert (zero-cost) between [`triomphe::Arc`] <-> [`Arc`] (and [`UniqueArc`] <-> [`triomphe::UniqueArc`]).
 //! - `unsize` use [`Arc`] together with the [`unsize`](https://crates.io/crates/unsize) crate.
+//! - `ffi`: Generate `extern "C"` functions (and their C declarations) to retain and release [`OffsetArc`]s from C or C++, see the [`ffi`] module.
+//! - `bytes`: Convert (zero-copy) an `Arc<[u8]>`, `Arc<str>`, `Arc<HeaderSlice<H, [u8]>>` or [`ArcSlice<u8>`](ArcSlice) into a [`bytes::Bytes`](https://docs.rs/bytes/latest/bytes/struct.Bytes.html), which releases the [`Arc`] when dropped.
 //! - `yoke`: Implements the [`CloneableCart`](https://docs.rs/yoke/0.6.2/yoke/trait.CloneableCart.html) trait from the the [`yoke`](https://crates.io/crates/yoke) crate for [`Arc`], making it easier to use in zero-copy serialization scenarios.
 //!
 //!
@@ -66,8 +80,12 @@ extern crate core;
 
 #[cfg(feature = "arc-swap")]
 extern crate arc_swap;
+#[cfg(feature = "bytes")]
+extern crate bytes;
 #[cfg(feature = "serde")]
 extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_test;
 #[cfg(feature = "stable_deref_trait")]
 extern crate stable_deref_trait;
 #[cfg(feature = "unsize")]
@@ -75,6 +93,9 @@ extern crate unsize;
 
 extern crate backdrop;
 
+/// Re-exported so that custom allocators (and the `Box<ArcInner<T>, A>` received by a backdrop strategy) can be named.
+pub extern crate allocator_api2;
+
 /// Calculates the offset of the specified field from the start of the named struct.
 /// This macro is impossible to be const until feature(const_ptr_offset_from) is stable.
 macro_rules! offset_of {
@@ -101,23 +122,53 @@ macro_rules! offset_of {
 
 mod arc;
 mod arc_borrow;
+mod arc_slice;
 #[cfg(feature = "arc-swap")]
 mod arc_swap_support;
 mod arc_union;
+mod builder;
+#[cfg(feature = "bytes")]
+mod bytes_support;
+mod dyn_strategy;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod header;
+#[cfg(feature = "std")]
+mod interner;
 mod iterator_as_exact_size_iterator;
+mod mapped_arc;
 mod offset_arc;
-// mod thin_arc;
+mod rc;
+mod rc_borrow;
+#[cfg(all(feature = "serde", feature = "std"))]
+pub mod serde_shared;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod thin_arc;
+mod threshold_strategy;
 mod unique_arc;
+mod unique_rc;
 
 pub use arc::*;
 pub use arc_borrow::*;
+pub use arc_slice::*;
 pub use arc_union::*;
+pub use backdrop::*;
+pub use builder::*;
+pub use dyn_strategy::*;
 pub use header::*;
+#[cfg(feature = "std")]
+pub use interner::*;
+pub use mapped_arc::*;
 pub use offset_arc::*;
-// pub use thin_arc::*;
-pub use backdrop::*;
+pub use rc::*;
+pub use rc_borrow::*;
+pub use thin_arc::*;
+pub use threshold_strategy::*;
 pub use unique_arc::*;
+pub use unique_rc::*;
+
+pub use allocator_api2::alloc::{Allocator, Global};
 
 #[cfg(feature = "std")]
 use std::process::abort;
@@ -0,0 +1,347 @@
+use allocator_api2::boxed::Box;
+use core::borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+use super::{Arc, ArcBorrow, ArcInner};
+
+/// An owning projection into (a part of) the contents of an [`Arc`].
+///
+/// Created by [`Arc::map`] or [`Arc::try_map`].
+/// A `MappedArc<U, S>` derefs to `U`, and keeps the whole parent `Arc<T, S>` alive:
+/// cloning it bumps the refcount of the parent,
+/// and when the last `Arc` or `MappedArc` pointing into the parent goes out of scope,
+/// the backdrop strategy `S` of the parent is executed (on the parent's `Box<ArcInner<T>>`).
+///
+/// The type of the parent is erased, so projections out of different parents can be stored together.
+///
+/// ```
+/// use backdrop_arc::{Arc, MappedArc, TrivialStrategy};
+///
+/// struct Config {
+///     name: String,
+///     retries: u32,
+/// }
+///
+/// let config: Arc<Config, TrivialStrategy> = Arc::new(Config { name: "server".into(), retries: 3 });
+/// let name: MappedArc<str, TrivialStrategy> = Arc::map(config.clone(), |c| c.name.as_str());
+/// assert_eq!(&*name, "server");
+/// assert_eq!(Arc::count(&config), 2);
+///
+/// drop(config);
+/// // The `Config` is kept alive by `name`
+/// assert_eq!(&*name.clone(), "server");
+/// ```
+pub struct MappedArc<U: ?Sized, S> {
+    value: NonNull<U>,
+    parent: NonNull<()>,
+    vtable: &'static ParentVTable,
+    phantom: PhantomData<U>,
+    phantom_strategy: PhantomData<S>,
+}
+
+// The parent is required to be `Send + Sync` when the `MappedArc` is created.
+unsafe impl<U: ?Sized + Sync + Send, S> Send for MappedArc<U, S> {}
+unsafe impl<U: ?Sized + Sync + Send, S> Sync for MappedArc<U, S> {}
+
+/// The operations on the (type-erased) parent `Arc<T, S>` of a `MappedArc`.
+struct ParentVTable {
+    clone: unsafe fn(NonNull<()>),
+    drop: unsafe fn(NonNull<()>),
+}
+
+struct Parent<T, S>(PhantomData<(T, S)>);
+
+impl<T, S> Parent<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    const VTABLE: ParentVTable = ParentVTable {
+        clone: Self::clone_parent,
+        drop: Self::drop_parent,
+    };
+
+    /// # Safety
+    ///
+    /// `parent` must have been obtained from `Arc::<T, S>::into_raw_inner`,
+    /// and still hold on to its reference.
+    unsafe fn clone_parent(parent: NonNull<()>) {
+        let data = &(*parent.cast::<ArcInner<T>>().as_ptr()).data;
+        mem::forget(ArcBorrow::from_ref(data).clone_arc::<S>());
+    }
+
+    /// # Safety
+    ///
+    /// `parent` must have been obtained from `Arc::<T, S>::into_raw_inner`,
+    /// and is not used again afterwards.
+    unsafe fn drop_parent(parent: NonNull<()>) {
+        drop(Arc::<T, S>::from_raw_inner(parent.cast().as_ptr()));
+    }
+}
+
+impl<T, S> Arc<T, S>
+where
+    T: Send + Sync + 'static,
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    /// Projects the `Arc` to a reference into its contents,
+    /// returning a [`MappedArc`] which keeps the whole `Arc` alive.
+    ///
+    /// The refcount is not modified.
+    #[inline]
+    pub fn map<U: ?Sized, F>(this: Self, f: F) -> MappedArc<U, S>
+    where
+        F: FnOnce(&T) -> &U,
+    {
+        let value = NonNull::from(f(&this));
+        MappedArc::from_parts(this, value)
+    }
+
+    /// Like [`Arc::map`], but the projection may fail,
+    /// in which case the original `Arc` is returned.
+    ///
+    /// ```
+    /// use backdrop_arc::{Arc, TrivialStrategy};
+    ///
+    /// let list: Arc<Vec<u8>, TrivialStrategy> = Arc::new(vec![1, 2, 3]);
+    /// let list = Arc::try_map(list, |v| v.get(10)).unwrap_err();
+    /// let last = Arc::try_map(list, |v| v.last()).unwrap();
+    /// assert_eq!(*last, 3);
+    /// ```
+    #[inline]
+    pub fn try_map<U: ?Sized, F>(this: Self, f: F) -> Result<MappedArc<U, S>, Self>
+    where
+        F: FnOnce(&T) -> Option<&U>,
+    {
+        match f(&this).map(NonNull::from) {
+            Some(value) => Ok(MappedArc::from_parts(this, value)),
+            None => Err(this),
+        }
+    }
+}
+
+impl<U: ?Sized, S> MappedArc<U, S> {
+    /// `value` must point into the contents of `parent`.
+    #[inline]
+    fn from_parts<T>(parent: Arc<T, S>, value: NonNull<U>) -> Self
+    where
+        T: Send + Sync + 'static,
+        S: BackdropStrategy<Box<ArcInner<T>>>,
+    {
+        // Safety: `ArcInner` pointers are never null
+        let parent = unsafe { NonNull::new_unchecked(Arc::into_raw_inner(parent) as *mut ()) };
+        MappedArc {
+            value,
+            parent,
+            vtable: &Parent::<T, S>::VTABLE,
+            phantom: PhantomData,
+            phantom_strategy: PhantomData,
+        }
+    }
+
+    /// Projects further into the contents, keeping the same parent alive.
+    ///
+    /// The refcount is not modified.
+    #[inline]
+    pub fn map<V: ?Sized, F>(this: Self, f: F) -> MappedArc<V, S>
+    where
+        F: FnOnce(&U) -> &V,
+    {
+        let value = NonNull::from(f(&this));
+        MappedArc::reproject(this, value)
+    }
+
+    /// Like [`MappedArc::map`], but the projection may fail,
+    /// in which case the original `MappedArc` is returned.
+    #[inline]
+    pub fn try_map<V: ?Sized, F>(this: Self, f: F) -> Result<MappedArc<V, S>, Self>
+    where
+        F: FnOnce(&U) -> Option<&V>,
+    {
+        match f(&this).map(NonNull::from) {
+            Some(value) => Ok(MappedArc::reproject(this, value)),
+            None => Err(this),
+        }
+    }
+
+    /// `value` must point into the contents of the parent of `this`.
+    #[inline]
+    fn reproject<V: ?Sized>(this: Self, value: NonNull<V>) -> MappedArc<V, S> {
+        let this = ManuallyDrop::new(this);
+        MappedArc {
+            value,
+            parent: this.parent,
+            vtable: this.vtable,
+            phantom: PhantomData,
+            phantom_strategy: PhantomData,
+        }
+    }
+
+    /// Test whether the two `MappedArc`s point into the same parent allocation.
+    ///
+    /// (They might still be projections to different parts of it.)
+    #[inline]
+    pub fn parent_ptr_eq<V: ?Sized>(this: &Self, other: &MappedArc<V, S>) -> bool {
+        this.parent == other.parent
+    }
+}
+
+impl<U: ?Sized, S> Deref for MappedArc<U, S> {
+    type Target = U;
+
+    #[inline]
+    fn deref(&self) -> &U {
+        // Safety: the parent containing the value is kept alive by `self`
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<U: ?Sized, S> Clone for MappedArc<U, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        // Safety: `self` holds on to a reference of the parent
+        unsafe { (self.vtable.clone)(self.parent) };
+        MappedArc {
+            value: self.value,
+            parent: self.parent,
+            vtable: self.vtable,
+            phantom: PhantomData,
+            phantom_strategy: PhantomData,
+        }
+    }
+}
+
+impl<U: ?Sized, S> Drop for MappedArc<U, S> {
+    #[inline]
+    fn drop(&mut self) {
+        // Safety: `self` holds on to a reference of the parent, and is not used again
+        unsafe { (self.vtable.drop)(self.parent) }
+    }
+}
+
+impl<U: ?Sized + PartialEq, S> PartialEq for MappedArc<U, S> {
+    fn eq(&self, other: &MappedArc<U, S>) -> bool {
+        *(*self) == *(*other)
+    }
+}
+
+impl<U: ?Sized + Eq, S> Eq for MappedArc<U, S> {}
+
+impl<U: ?Sized + PartialOrd, S> PartialOrd for MappedArc<U, S> {
+    fn partial_cmp(&self, other: &MappedArc<U, S>) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<U: ?Sized + Ord, S> Ord for MappedArc<U, S> {
+    fn cmp(&self, other: &MappedArc<U, S>) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<U: ?Sized + Hash, S> Hash for MappedArc<U, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<U: ?Sized + fmt::Display, S> fmt::Display for MappedArc<U, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<U: ?Sized + fmt::Debug, S> fmt::Debug for MappedArc<U, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<U: ?Sized, S> fmt::Pointer for MappedArc<U, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.value, f)
+    }
+}
+
+impl<U: ?Sized, S> borrow::Borrow<U> for MappedArc<U, S> {
+    #[inline]
+    fn borrow(&self) -> &U {
+        self
+    }
+}
+
+impl<U: ?Sized, S> AsRef<U> for MappedArc<U, S> {
+    #[inline]
+    fn as_ref(&self) -> &U {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::{LeakStrategy, TrivialStrategy};
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    use crate::{Arc, MappedArc};
+
+    struct Config {
+        name: String,
+        ports: Vec<u16>,
+    }
+
+    #[test]
+    fn map_keeps_parent_alive() {
+        let config: Arc<Config, TrivialStrategy> = Arc::new(Config {
+            name: String::from("server"),
+            ports: vec![80, 443],
+        });
+
+        let name = Arc::map(config.clone(), |c| c.name.as_str());
+        let ports = Arc::map(config.clone(), |c| &c.ports[..]);
+        assert_eq!(Arc::count(&config), 3);
+
+        let https = MappedArc::try_map(ports.clone(), |p| p.get(1)).unwrap();
+        assert!(MappedArc::parent_ptr_eq(&ports, &https));
+        assert_eq!(Arc::count(&config), 4);
+        let ports = MappedArc::try_map(ports, |p| p.get(5)).unwrap_err();
+
+        drop(config);
+        assert_eq!(&*name, "server");
+        assert_eq!(*ports, [80, 443]);
+        assert_eq!(*https, 443);
+    }
+
+    #[test]
+    fn map_runs_parent_strategy() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        struct Counted(u8);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, SeqCst);
+            }
+        }
+
+        let arc: Arc<Counted, TrivialStrategy> = Arc::new(Counted(1));
+        let field = Arc::map(arc, |c| &c.0);
+        let field2 = field.clone();
+        drop(field);
+        assert_eq!(DROPS.load(SeqCst), 0);
+        drop(field2);
+        assert_eq!(DROPS.load(SeqCst), 1);
+
+        let leaked: Arc<Counted, LeakStrategy> = Arc::new(Counted(2));
+        drop(Arc::map(leaked, |c| &c.0));
+        assert_eq!(DROPS.load(SeqCst), 1);
+    }
+}
@@ -1,5 +1,9 @@
-use alloc::boxed::Box;
+use allocator_api2::boxed::Box;
+use core::cmp::Ordering;
+use core::ffi::c_void;
 use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::ExactSizeIterator;
 use core::marker::PhantomData;
 use core::mem::ManuallyDrop;
 use core::ops::Deref;
@@ -10,7 +14,12 @@ use crate::ArcInner;
 
 use self::backdrop::BackdropStrategy;
 
-use super::{Arc, ArcBorrow};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "stable_deref_trait")]
+use stable_deref_trait::{CloneStableDeref, StableDeref};
+
+use super::{Arc, ArcBorrow, HeaderSlice, HeaderWithLength, ThinPointee};
 
 /// An `Arc`, except it holds a pointer to the T instead of to the
 /// entire ArcInner.
@@ -34,32 +43,51 @@ use super::{Arc, ArcBorrow};
 /// This is very useful if you have an Arc-containing struct shared between Rust and C++,
 /// and wish for C++ to be able to read the data behind the `Arc` without incurring
 /// an FFI call overhead.
-#[derive(Eq)]
+///
+/// Besides sized types, `T` can be any [`ThinPointee`], like a
+/// `HeaderSlice<HeaderWithLength<H>, [T]>`. Its length is stored in the header,
+/// so the pointer is still a single (thin) C pointer:
+///
+/// ```
+/// use backdrop_arc::{HeaderSlice, HeaderWithLength, OffsetArc, TrivialStrategy};
+///
+/// let arc: OffsetArc<HeaderSlice<HeaderWithLength<u8>, [u32]>, TrivialStrategy> =
+///     OffsetArc::from_header_and_slice(42, &[1, 2, 3]);
+/// assert_eq!(std::mem::size_of_val(&arc), std::mem::size_of::<usize>());
+/// assert_eq!(arc.header.header, 42);
+/// assert_eq!(arc.slice, [1, 2, 3]);
+/// ```
 #[repr(transparent)]
-pub struct OffsetArc<T, S>
+pub struct OffsetArc<T: ?Sized + ThinPointee, S>
 where
     S: BackdropStrategy<Box<ArcInner<T>>>,
 {
-    pub(crate) ptr: ptr::NonNull<T>,
+    pub(crate) ptr: ptr::NonNull<()>,
     pub(crate) phantom: PhantomData<T>,
     pub(crate) phantom_strategy: PhantomData<S>,
 }
 
-unsafe impl<T: Sync + Send, S> Send for OffsetArc<T, S> where S: BackdropStrategy<Box<ArcInner<T>>> {}
-unsafe impl<T: Sync + Send, S> Sync for OffsetArc<T, S> where S: BackdropStrategy<Box<ArcInner<T>>> {}
+unsafe impl<T: ?Sized + ThinPointee + Sync + Send, S> Send for OffsetArc<T, S> where
+    S: BackdropStrategy<Box<ArcInner<T>>>
+{
+}
+unsafe impl<T: ?Sized + ThinPointee + Sync + Send, S> Sync for OffsetArc<T, S> where
+    S: BackdropStrategy<Box<ArcInner<T>>>
+{
+}
 
-impl<T, S> Deref for OffsetArc<T, S>
+impl<T: ?Sized + ThinPointee, S> Deref for OffsetArc<T, S>
 where
     S: BackdropStrategy<Box<ArcInner<T>>>,
 {
     type Target = T;
     #[inline]
     fn deref(&self) -> &Self::Target {
-        unsafe { &*self.ptr.as_ptr() }
+        unsafe { &(*self.inner()).data }
     }
 }
 
-impl<T, S> Clone for OffsetArc<T, S>
+impl<T: ?Sized + ThinPointee, S> Clone for OffsetArc<T, S>
 where
     S: BackdropStrategy<Box<ArcInner<T>>>,
 {
@@ -69,7 +97,7 @@ where
     }
 }
 
-impl<T, S> Drop for OffsetArc<T, S>
+impl<T: ?Sized + ThinPointee, S> Drop for OffsetArc<T, S>
 where
     S: BackdropStrategy<Box<ArcInner<T>>>,
 {
@@ -82,7 +110,7 @@ where
     }
 }
 
-impl<T: fmt::Debug, S> fmt::Debug for OffsetArc<T, S>
+impl<T: ?Sized + ThinPointee + fmt::Debug, S> fmt::Debug for OffsetArc<T, S>
 where
     S: BackdropStrategy<Box<ArcInner<T>>>,
 {
@@ -91,7 +119,26 @@ where
     }
 }
 
-impl<T: PartialEq, S> PartialEq for OffsetArc<T, S>
+impl<T: ?Sized + ThinPointee + fmt::Display, S> fmt::Display for OffsetArc<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// Formats the pointer to the data, i.e. the pointer that is passed to C.
+impl<T: ?Sized + ThinPointee, S> fmt::Pointer for OffsetArc<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.ptr, f)
+    }
+}
+
+impl<T: ?Sized + ThinPointee + PartialEq, S> PartialEq for OffsetArc<T, S>
 where
     S: BackdropStrategy<Box<ArcInner<T>>>,
 {
@@ -105,10 +152,128 @@ where
     }
 }
 
-impl<T, S> OffsetArc<T, S>
+impl<T: ?Sized + ThinPointee + Eq, S> Eq for OffsetArc<T, S> where
+    S: BackdropStrategy<Box<ArcInner<T>>>
+{
+}
+
+impl<T: ?Sized + ThinPointee + PartialOrd, S> PartialOrd for OffsetArc<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    fn partial_cmp(&self, other: &OffsetArc<T, S>) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + ThinPointee + Ord, S> Ord for OffsetArc<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    fn cmp(&self, other: &OffsetArc<T, S>) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + ThinPointee + Hash, S> Hash for OffsetArc<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>, S> Deserialize<'de> for OffsetArc<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<OffsetArc<T, S>, D::Error>
+    where
+        D: ::serde::de::Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(|data| Arc::into_raw_offset(Arc::new(data)))
+    }
+}
+
+/// Uses the same format as an `Arc<HeaderSlice<H, [T]>>`; the length in the header is taken from the slice.
+#[cfg(feature = "serde")]
+impl<'de, H, T, S> Deserialize<'de> for OffsetArc<HeaderSlice<HeaderWithLength<H>, [T]>, S>
+where
+    H: Deserialize<'de>,
+    T: Deserialize<'de>,
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<HeaderWithLength<H>, [T]>>>>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::de::Deserializer<'de>,
+    {
+        let (header, items) = crate::serde_support::deserialize_header_and_items(deserializer)?;
+        Ok(OffsetArc::from_header_and_iter(header, items.into_iter()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: ?Sized + ThinPointee + Serialize, S> Serialize for OffsetArc<T, S>
 where
     S: BackdropStrategy<Box<ArcInner<T>>>,
 {
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: ::serde::ser::Serializer,
+    {
+        (**self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "stable_deref_trait")]
+unsafe impl<T: ?Sized + ThinPointee, S> StableDeref for OffsetArc<T, S> where
+    S: BackdropStrategy<Box<ArcInner<T>>>
+{
+}
+#[cfg(feature = "stable_deref_trait")]
+unsafe impl<T: ?Sized + ThinPointee, S> CloneStableDeref for OffsetArc<T, S> where
+    S: BackdropStrategy<Box<ArcInner<T>>>
+{
+}
+
+impl<T: ?Sized + ThinPointee, S> OffsetArc<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    /// The pointer to the `ArcInner`, found by removing the offset.
+    #[inline]
+    pub(crate) fn inner(&self) -> *mut ArcInner<T> {
+        unsafe {
+            let inner = (self.ptr.as_ptr() as *mut u8).sub(T::data_offset());
+            T::thicken(inner as *mut ())
+        }
+    }
+
+    /// Consumes the `OffsetArc`, returning the pointer to its data,
+    /// e.g. to hand it to C. The refcount is not modified.
+    #[inline]
+    pub fn into_raw(this: Self) -> *const c_void {
+        let this = ManuallyDrop::new(this);
+        this.ptr.as_ptr() as *const c_void
+    }
+
+    /// Reconstructs an `OffsetArc` from a pointer returned by [`OffsetArc::into_raw`].
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been obtained from `OffsetArc::<T, S>::into_raw`,
+    /// and the reference it represents must not be used again afterwards.
+    #[inline]
+    pub unsafe fn from_raw(ptr: *const c_void) -> Self {
+        OffsetArc {
+            ptr: ptr::NonNull::new_unchecked(ptr as *mut ()),
+            phantom: PhantomData,
+            phantom_strategy: PhantomData,
+        }
+    }
+
     /// Temporarily converts |self| into a bonafide Arc and exposes it to the
     /// provided callback. The refcount is not modified.
     #[inline]
@@ -117,7 +282,7 @@ where
         F: FnOnce(&Arc<T, S>) -> U,
     {
         // Synthesize transient Arc, which never touches the refcount of the ArcInner.
-        let transient = unsafe { ManuallyDrop::new(Arc::from_raw(self.ptr.as_ptr())) };
+        let transient = unsafe { ManuallyDrop::new(Arc::from_raw_inner(self.inner())) };
 
         // Expose the transient Arc to the callback, which may clone it if it wants
         // and forward the result to the user
@@ -158,6 +323,140 @@ where
     /// to an `Arc`
     #[inline]
     pub fn borrow_arc(&self) -> ArcBorrow<'_, T> {
-        ArcBorrow(&**self)
+        ArcBorrow(&**self, PhantomData)
+    }
+}
+
+impl<H, T, S> OffsetArc<HeaderSlice<HeaderWithLength<H>, [T]>, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<HeaderWithLength<H>, [T]>>>>,
+{
+    /// Creates an `OffsetArc` for a HeaderSlice using the given header struct and
+    /// iterator to generate the slice.
+    pub fn from_header_and_iter<I>(header: H, items: I) -> Self
+    where
+        I: Iterator<Item = T> + ExactSizeIterator,
+    {
+        Arc::into_raw_offset(Arc::<_, S>::from_header_with_length_and_iter(header, items))
+    }
+
+    /// Creates an `OffsetArc` for a HeaderSlice using the given header struct and
+    /// a slice to copy.
+    pub fn from_header_and_slice(header: H, items: &[T]) -> Self
+    where
+        T: Copy,
+    {
+        let header = HeaderWithLength::new(header, items.len());
+        Arc::into_raw_offset(Arc::<_, S>::from_header_and_slice(header, items))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::TrivialStrategy;
+    use super::OffsetArc;
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+    use core::mem;
+
+    use crate::{Arc, HeaderSlice, HeaderWithLength};
+
+    type Node = HeaderSlice<HeaderWithLength<&'static str>, [String]>;
+
+    #[test]
+    fn offset_arc_points_to_data() {
+        let arc = Arc::<_, TrivialStrategy>::new(42u64);
+        let offset = Arc::into_raw_offset(arc.clone());
+        assert_eq!(mem::size_of_val(&offset), mem::size_of::<usize>());
+        assert_eq!(format_ptr(&offset), &*arc as *const u64 as usize);
+        assert_eq!(*offset, 42);
+        assert_eq!(Arc::count(&arc), 2);
+        drop(offset);
+        assert_eq!(Arc::count(&arc), 1);
+    }
+
+    #[test]
+    fn offset_arc_header_slice() {
+        let items = ["a", "b", "c"].iter().map(|x| x.to_string());
+        let offset: OffsetArc<Node, TrivialStrategy> =
+            OffsetArc::from_header_and_iter("node", items);
+        assert_eq!(mem::size_of_val(&offset), mem::size_of::<usize>());
+        assert_eq!(offset.header.header, "node");
+        assert_eq!(offset.header.length, 3);
+        assert_eq!(offset.slice, ["a", "b", "c"]);
+
+        // The pointer points to the header, and the slice follows it.
+        assert_eq!(format_ptr(&offset), &offset.header as *const _ as usize);
+
+        let cloned = offset.clone();
+        assert_eq!(offset, cloned);
+        let arc = Arc::from_raw_offset(cloned);
+        assert_eq!(Arc::count(&arc), 2);
+        assert_eq!(arc.slice.iter().collect::<Vec<_>>(), ["a", "b", "c"]);
+        drop(offset);
+        assert_eq!(Arc::count(&arc), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Length needs to be correct")]
+    fn offset_arc_checks_header_length() {
+        let arc: Arc<HeaderSlice<HeaderWithLength<u8>, [u64]>, TrivialStrategy> =
+            Arc::from_header_and_slice(HeaderWithLength::new(0, 100_000), &[1]);
+        Arc::into_raw_offset(arc);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn offset_arc_header_slice_serde() {
+        use serde_test::{assert_de_tokens, assert_tokens, Token};
+
+        let items = ["a", "b"].iter().map(|x| x.to_string());
+        let offset: OffsetArc<HeaderSlice<HeaderWithLength<u8>, [String]>, TrivialStrategy> =
+            OffsetArc::from_header_and_iter(7, items);
+        assert_tokens(
+            &offset,
+            &[
+                Token::Struct {
+                    name: "HeaderSlice",
+                    len: 2,
+                },
+                Token::Str("header"),
+                Token::U8(7),
+                Token::Str("slice"),
+                Token::Seq { len: Some(2) },
+                Token::Str("a"),
+                Token::Str("b"),
+                Token::SeqEnd,
+                Token::StructEnd,
+            ],
+        );
+        // Without a known length, the length in the header still matches the slice
+        assert_de_tokens(
+            &offset,
+            &[
+                Token::Seq { len: Some(2) },
+                Token::U8(7),
+                Token::Seq { len: None },
+                Token::Str("a"),
+                Token::Str("b"),
+                Token::SeqEnd,
+                Token::SeqEnd,
+            ],
+        );
+        assert_eq!(offset.header.length, 2);
+    }
+
+    #[test]
+    fn offset_arc_ordering() {
+        let a: OffsetArc<u8, TrivialStrategy> = Arc::into_raw_offset(Arc::new(1));
+        let b: OffsetArc<u8, TrivialStrategy> = Arc::into_raw_offset(Arc::new(2));
+        assert!(a < b);
+        assert_eq!(a.cmp(&a.clone()), core::cmp::Ordering::Equal);
+    }
+
+    fn format_ptr<T: ?Sized + crate::ThinPointee>(offset: &OffsetArc<T, TrivialStrategy>) -> usize {
+        let mut formatted = String::new();
+        core::fmt::write(&mut formatted, format_args!("{:p}", *offset)).unwrap();
+        usize::from_str_radix(formatted.trim_start_matches("0x"), 16).unwrap()
     }
 }
@@ -0,0 +1,903 @@
+use alloc::alloc::handle_alloc_error;
+use alloc::string::String;
+use alloc::vec::Vec;
+use allocator_api2::alloc::{Allocator, Global};
+use allocator_api2::boxed::Box;
+use backdrop::Backdrop;
+use core::alloc::Layout;
+use core::borrow;
+use core::cell::Cell;
+use core::cmp::Ordering;
+use core::convert::From;
+use core::ffi::c_void;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::{ExactSizeIterator, FromIterator, FusedIterator, Iterator};
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
+use core::ptr::{self, addr_of_mut, NonNull};
+
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+use crate::header::{set_data_ptr, PartialHeaderSlice};
+use crate::iterator_as_exact_size_iterator::IteratorAsExactSizeIterator;
+use crate::{abort, AllocError, HeaderSlice, RcBorrow, UniqueRc};
+
+/// A soft limit on the amount of references that may be made to an `Rc`.
+const MAX_REFCOUNT: usize = (isize::MAX) as usize;
+
+/// The internal object allocated by an Rc<T, S>.
+///
+/// (The structure which contains the (non-atomic) reference count and `T` itself.)
+///
+/// Its internals are hidden, but the type is made public
+/// because you will receive a `Box<RcInner<T>>` when backdropping.
+#[derive(Debug)]
+#[repr(C)]
+pub struct RcInner<T: ?Sized> {
+    pub(crate) count: Cell<usize>,
+    pub(crate) data: T,
+}
+
+/// A non-atomically reference counted pointer, the single-threaded counterpart of [`Arc`](crate::Arc).
+///
+/// This works like a `std::rc::Rc<T>` without weak pointers,
+/// except that when the last clone goes out of scope, the [`BackdropStrategy`] `S` is executed
+/// (on the `Box<RcInner<T>>`), just like for an [`Arc`](crate::Arc).
+///
+/// Because the reference count is a `Cell<usize>`, `Rc` is neither `Send` nor `Sync`,
+/// but cloning and dropping it is cheaper.
+///
+/// ```
+/// use backdrop_arc::{Rc, TrivialStrategy};
+///
+/// let a: Rc<Vec<u32>, TrivialStrategy> = Rc::new(vec![1, 2, 3]);
+/// let b = a.clone();
+/// assert_eq!(Rc::count(&a), 2);
+/// assert!(Rc::ptr_eq(&a, &b));
+/// ```
+#[repr(transparent)]
+pub struct Rc<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> {
+    pub(crate) p: NonNull<RcInner<T>>,
+    pub(crate) phantom: PhantomData<T>,
+    pub(crate) phantom_strategy: PhantomData<S>,
+}
+
+impl<T, S: BackdropStrategy<Box<RcInner<T>>>> Rc<T, S> {
+    /// Construct an `Rc<T, S>`
+    #[inline]
+    pub fn new(data: T) -> Self {
+        let layout = Layout::new::<RcInner<T>>();
+        Rc::try_new(data).unwrap_or_else(|_| handle_alloc_error(layout))
+    }
+
+    /// Construct an `Rc<T, S>`, returning an error if allocation fails.
+    #[inline]
+    pub fn try_new(data: T) -> Result<Self, AllocError> {
+        // Safety: the closure only changes the type of the pointer
+        let inner = unsafe {
+            Rc::<_, S>::try_allocate_for_layout(Layout::new::<T>(), |mem| mem as *mut RcInner<T>)?
+        };
+
+        unsafe {
+            // Safety: `inner` is freshly allocated, so the `data` field is
+            // valid for writes and not yet initialized.
+            ptr::write(addr_of_mut!((*inner.as_ptr()).data), data);
+
+            Ok(Rc::from_raw_inner(inner.as_ptr()))
+        }
+    }
+
+    /// Reconstruct the Rc<T, S> from a raw pointer obtained from into_raw()
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been obtained from `Rc::<T, S>::into_raw`,
+    /// and the reference it represents must not be used again afterwards.
+    #[inline]
+    pub unsafe fn from_raw(ptr: *const T) -> Self {
+        let ptr = (ptr as *const u8).sub(offset_of!(RcInner<T>, data));
+        Rc::from_raw_inner(ptr as *mut RcInner<T>)
+    }
+
+    /// Returns the inner value, if the [`Rc`] has exactly one strong reference.
+    ///
+    /// Otherwise, an [`Err`] is returned with the same [`Rc`] that was
+    /// passed in.
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        Self::try_unique(this).map(UniqueRc::into_inner)
+    }
+
+    /// Alter the strategy that is used for an Rc<T, S> to another.
+    /// This is a zero-cost operation.
+    pub fn with_strategy<S2: BackdropStrategy<Box<RcInner<T>>>>(rc: Rc<T, S>) -> Rc<T, S2> {
+        // Safety: S and S2 are ZSTs which only do something at drop-time
+        unsafe { Rc::from_raw_inner(Rc::into_raw_inner(rc)) }
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> Rc<T, S> {
+    /// Convert the Rc<T, S> to a raw pointer
+    ///
+    /// Note: This returns a pointer to the data T, which is offset in the allocation.
+    #[inline]
+    pub fn into_raw(this: Self) -> *const T {
+        let this = ManuallyDrop::new(this);
+        this.as_ptr()
+    }
+
+    /// Returns the raw pointer.
+    ///
+    /// Same as into_raw except `self` isn't consumed.
+    #[inline]
+    pub fn as_ptr(&self) -> *const T {
+        // SAFETY: This cannot go through a reference to `data`, because this method
+        // is used to implement `into_raw`.
+        unsafe { addr_of_mut!((*self.ptr()).data) }
+    }
+
+    /// Returns the address on the heap of the Rc itself -- not the T within it -- for memory
+    /// reporting.
+    pub fn heap_ptr(&self) -> *const c_void {
+        self.p.as_ptr() as *const RcInner<T> as *const c_void
+    }
+
+    #[inline]
+    pub(super) fn into_raw_inner(this: Self) -> *mut RcInner<T> {
+        let this = ManuallyDrop::new(this);
+        this.ptr()
+    }
+
+    /// Construct an `Rc` from an allocated `RcInner`.
+    /// # Safety
+    /// The `ptr` must point to a valid instance, allocated by an `Rc`. The reference count will
+    /// not be modified.
+    pub(super) unsafe fn from_raw_inner(ptr: *mut RcInner<T>) -> Self {
+        Rc {
+            p: NonNull::new_unchecked(ptr),
+            phantom: PhantomData,
+            phantom_strategy: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub(super) fn inner(&self) -> &RcInner<T> {
+        // Safety: while this rc is alive we're guaranteed that the inner pointer is valid.
+        unsafe { &*self.ptr() }
+    }
+
+    pub(crate) fn ptr(&self) -> *mut RcInner<T> {
+        self.p.as_ptr()
+    }
+
+    /// Produce a pointer to the data that can be converted back
+    /// to an Rc. This is basically an `&Rc<T, S>`, without the extra indirection.
+    #[inline]
+    pub fn borrow_rc(&self) -> RcBorrow<'_, T> {
+        RcBorrow(&**self)
+    }
+
+    /// Test pointer equality between the two Rcs, i.e. they must be the _same_
+    /// allocation
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr() as *const () == other.ptr() as *const ()
+    }
+
+    /// Gets the number of [`Rc`] pointers to this allocation
+    pub fn count(this: &Self) -> usize {
+        this.inner().count.get()
+    }
+
+    /// Whether or not the `Rc` is uniquely owned (is the refcount 1?).
+    pub fn is_unique(&self) -> bool {
+        Self::count(self) == 1
+    }
+
+    /// Provides mutable access to the contents _if_ the `Rc` is uniquely owned.
+    #[inline]
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if this.is_unique() {
+            // Safety: we are the only reference
+            unsafe { Some(&mut (*this.ptr()).data) }
+        } else {
+            None
+        }
+    }
+
+    /// Provides unique access to the rc _if_ the `Rc` is uniquely owned.
+    pub fn get_unique(this: &mut Self) -> Option<&mut UniqueRc<T, S>> {
+        if this.is_unique() {
+            // Safety: The current rc is unique
+            unsafe { Some(UniqueRc::from_rc_ref(this)) }
+        } else {
+            None
+        }
+    }
+
+    /// Returns a [`UniqueRc`] if the [`Rc`] has exactly one strong reference.
+    ///
+    /// Otherwise, an [`Err`] is returned with the same [`Rc`] that was
+    /// passed in.
+    pub fn try_unique(this: Self) -> Result<UniqueRc<T, S>, Self> {
+        if this.is_unique() {
+            // Safety: The current rc is unique and making a `UniqueRc`
+            //         from it is sound
+            unsafe { Ok(UniqueRc::from_rc(this)) }
+        } else {
+            Err(this)
+        }
+    }
+
+    /// Optimization over calling `clone()` many times:
+    /// the reference count is increased by `count` _once_.
+    ///
+    /// The resulting iterator gives out exactly `count` `Rc`s.
+    /// If it is dropped before all of them are given out,
+    /// the reference count is decreased by the leftover amount.
+    ///
+    /// ```
+    /// use backdrop_arc::{Rc, TrivialStrategy};
+    ///
+    /// let myrc: Rc<u32, TrivialStrategy> = Rc::new(42);
+    /// let many_clones: Vec<_> = Rc::clone_many(&myrc, 1000).take(100).collect();
+    /// assert_eq!(Rc::count(&myrc), 101);
+    /// ```
+    pub fn clone_many(this: &Self, count: usize) -> RcCloneIter<'_, T, S> {
+        RcCloneIter::new(this, count)
+    }
+
+    // Non-inlined part of `drop`. Just invokes the destructor.
+    #[inline(never)]
+    unsafe fn drop_slow(&mut self) {
+        let _ = Backdrop::<_, S>::new(Box::from_raw(self.ptr()));
+    }
+
+    /// Allocates an `RcInner<T>` with sufficient space for
+    /// a possibly-unsized inner value where the value has the layout provided,
+    /// returning an error if allocation fails.
+    ///
+    /// ## Safety
+    ///
+    /// `mem_to_rcinner` must return the same pointer, the only things that can change are
+    /// - its type
+    /// - its metadata
+    ///
+    /// `value_layout` must be correct for `T`.
+    #[allow(unused_unsafe)]
+    pub(super) unsafe fn try_allocate_for_layout(
+        value_layout: Layout,
+        mem_to_rcinner: impl FnOnce(*mut u8) -> *mut RcInner<T>,
+    ) -> Result<NonNull<RcInner<T>>, AllocError> {
+        let layout = Layout::new::<RcInner<()>>()
+            .extend(value_layout)
+            .map_err(|_| AllocError)?
+            .0
+            .pad_to_align();
+
+        let ptr = Global
+            .allocate(layout)
+            .map_err(|_| AllocError)?
+            .cast::<u8>();
+
+        // Initialize the RcInner
+        let inner = mem_to_rcinner(ptr.as_ptr());
+        debug_assert_eq!(unsafe { Layout::for_value(&*inner) }, layout);
+
+        unsafe {
+            ptr::write(&mut (*inner).count, Cell::new(1));
+        }
+
+        // Safety: `ptr` is checked to be non-null,
+        //         `inner` is the same as `ptr` (per the safety requirements of this function)
+        unsafe { Ok(NonNull::new_unchecked(inner)) }
+    }
+
+    /// Like `try_allocate_for_layout`, but calls `handle_alloc_error` on failure.
+    ///
+    /// ## Safety
+    ///
+    /// See `try_allocate_for_layout`.
+    pub(super) unsafe fn allocate_for_layout(
+        value_layout: Layout,
+        mem_to_rcinner: impl FnOnce(*mut u8) -> *mut RcInner<T>,
+    ) -> NonNull<RcInner<T>> {
+        Rc::<_, S>::try_allocate_for_layout(value_layout, mem_to_rcinner).unwrap_or_else(|_| {
+            handle_alloc_error(
+                Layout::new::<RcInner<()>>()
+                    .extend(value_layout)
+                    .map(|(layout, _)| layout)
+                    .unwrap_or(value_layout),
+            )
+        })
+    }
+
+    #[inline]
+    fn increment(&self, count: usize) {
+        let inner = self.inner();
+        // Guard against overflow, just like `Arc::clone` does.
+        let val = inner
+            .count
+            .get()
+            .checked_add(count)
+            .unwrap_or_else(|| abort());
+        if val > MAX_REFCOUNT {
+            abort();
+        }
+        inner.count.set(val);
+    }
+}
+
+impl<T: Clone, S: BackdropStrategy<Box<RcInner<T>>>> Rc<T, S> {
+    /// Makes a mutable reference to the `Rc`, cloning if necessary
+    ///
+    /// If this `Rc` is uniquely owned, `make_mut()` will provide a mutable
+    /// reference to the contents. If not, `make_mut()` will create a _new_ `Rc`
+    /// with a copy of the contents, update `this` to point to it, and provide
+    /// a mutable reference to its contents.
+    #[inline]
+    pub fn make_mut(this: &mut Self) -> &mut T {
+        if !this.is_unique() {
+            // Another pointer exists; clone
+            *this = Rc::new(T::clone(this));
+        }
+
+        // Safety: the refcount is 1 at this point, and we required the Rc itself to be `mut`
+        unsafe { &mut (*this.ptr()).data }
+    }
+
+    /// Makes a `UniqueRc` from an `Rc`, cloning if necessary.
+    #[inline]
+    pub fn make_unique(this: &mut Self) -> &mut UniqueRc<T, S> {
+        if !this.is_unique() {
+            // Another pointer exists; clone
+            *this = Rc::new(T::clone(this));
+        }
+
+        // Safety: this is either unique or just created (which is also unique)
+        unsafe { UniqueRc::from_rc_ref(this) }
+    }
+
+    /// If we have the only reference to `T` then unwrap it. Otherwise, clone `T` and return the clone.
+    pub fn unwrap_or_clone(this: Rc<T, S>) -> T {
+        Self::try_unwrap(this).unwrap_or_else(|this| T::clone(&this))
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> Clone for Rc<T, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.increment(1);
+        // Safety: we just incremented the refcount
+        unsafe { Rc::from_raw_inner(self.ptr()) }
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> Drop for Rc<T, S> {
+    #[inline]
+    fn drop(&mut self) {
+        let inner = self.inner();
+        let count = inner.count.get() - 1;
+        inner.count.set(count);
+        if count == 0 {
+            unsafe { self.drop_slow() }
+        }
+    }
+}
+
+/// Iterator type to give out many clones an rc without the overhead of calling `clone` every time.
+///
+/// Return type of [`Rc::clone_many`].
+#[derive(Debug, Hash)]
+pub struct RcCloneIter<'a, T: ?Sized, S>
+where
+    S: BackdropStrategy<Box<RcInner<T>>>,
+{
+    orig: &'a Rc<T, S>,
+    rcs_left: usize,
+}
+
+impl<'a, T: ?Sized, S> RcCloneIter<'a, T, S>
+where
+    S: BackdropStrategy<Box<RcInner<T>>>,
+{
+    #[inline]
+    fn new(orig: &'a Rc<T, S>, count: usize) -> Self {
+        orig.increment(count);
+        RcCloneIter {
+            orig,
+            rcs_left: count,
+        }
+    }
+}
+
+impl<'a, T: ?Sized, S> Clone for RcCloneIter<'a, T, S>
+where
+    S: BackdropStrategy<Box<RcInner<T>>>,
+{
+    /// The clone holds its own refcounts for the `Rc`s it has left to give out.
+    #[inline]
+    fn clone(&self) -> Self {
+        RcCloneIter::new(self.orig, self.rcs_left)
+    }
+}
+
+impl<'a, T: ?Sized, S> Drop for RcCloneIter<'a, T, S>
+where
+    S: BackdropStrategy<Box<RcInner<T>>>,
+{
+    #[inline]
+    fn drop(&mut self) {
+        // Since we have the reference `orig`, the refcount will always stay > 0
+        let count = &self.orig.inner().count;
+        count.set(count.get() - self.rcs_left);
+    }
+}
+
+impl<'a, T: ?Sized, S> Iterator for RcCloneIter<'a, T, S>
+where
+    S: BackdropStrategy<Box<RcInner<T>>>,
+{
+    type Item = Rc<T, S>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rcs_left == 0 {
+            return None;
+        }
+        self.rcs_left -= 1;
+
+        // SAFETY: we only make a new rc when there still are refcounts left to give out
+        Some(unsafe { Rc::from_raw_inner(self.orig.ptr()) })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.rcs_left, Some(self.rcs_left))
+    }
+}
+
+impl<'a, T: ?Sized, S> ExactSizeIterator for RcCloneIter<'a, T, S> where
+    S: BackdropStrategy<Box<RcInner<T>>>
+{
+}
+
+impl<'a, T: ?Sized, S> FusedIterator for RcCloneIter<'a, T, S> where
+    S: BackdropStrategy<Box<RcInner<T>>>
+{
+}
+
+impl<H, T, S> Rc<HeaderSlice<H, [T]>, S>
+where
+    S: BackdropStrategy<Box<RcInner<HeaderSlice<H, [T]>>>>,
+{
+    fn header_and_slice_layout(len: usize) -> Layout {
+        Layout::new::<H>()
+            .extend(Layout::array::<T>(len).expect("capacity overflow"))
+            .expect("capacity overflow")
+            .0
+            .pad_to_align()
+    }
+
+    fn allocate_for_header_and_slice(len: usize) -> NonNull<RcInner<HeaderSlice<H, [T]>>> {
+        let layout = Self::header_and_slice_layout(len);
+
+        unsafe {
+            // Safety:
+            // - the provided closure does not change the pointer (except for meta & type)
+            // - the provided layout is valid for `HeaderSlice<H, [T]>`
+            Rc::<_, S>::allocate_for_layout(layout, |mem| {
+                // Synthesize the fat pointer, see `Arc::allocate_for_header_and_slice`
+                let fake_slice = ptr::slice_from_raw_parts_mut(mem as *mut T, len);
+                fake_slice as *mut RcInner<HeaderSlice<H, [T]>>
+            })
+        }
+    }
+
+    /// Creates an Rc for a HeaderSlice using the given header struct and
+    /// iterator to generate the slice. The resulting Rc will be fat.
+    ///
+    /// If the iterator turns out to report the wrong length,
+    /// this falls back to collecting the items into a `Vec` first.
+    pub fn from_header_and_iter<I>(header: H, items: I) -> Self
+    where
+        I: Iterator<Item = T> + ExactSizeIterator,
+    {
+        let num_items = items.len();
+        let inner = Rc::<_, S>::allocate_for_header_and_slice(num_items);
+        let layout = Layout::new::<RcInner<()>>()
+            .extend(Self::header_and_slice_layout(num_items))
+            .expect("layout was allocated before")
+            .0
+            .pad_to_align();
+
+        unsafe {
+            // Safety: `inner` was just allocated (by `Global`) with `layout`
+            let written = PartialHeaderSlice::write(
+                inner.cast(),
+                layout,
+                addr_of_mut!((*inner.as_ptr()).data.header),
+                addr_of_mut!((*inner.as_ptr()).data.slice) as *mut T,
+                header,
+                num_items,
+                items,
+            );
+            match written {
+                // Safety: ptr is valid & the inner structure is fully initialized
+                Ok(()) => Rc::from_raw_inner(inner.as_ptr()),
+                Err((header, items)) => Self::from_header_and_vec(header, items),
+            }
+        }
+    }
+
+    /// Creates an Rc for a HeaderSlice using the given header struct and
+    /// a slice to copy. The resulting Rc will be fat.
+    pub fn from_header_and_slice(header: H, items: &[T]) -> Self
+    where
+        T: Copy,
+    {
+        let inner = Rc::<_, S>::allocate_for_header_and_slice(items.len());
+
+        unsafe {
+            // Write the data.
+            ptr::write(&mut ((*inner.as_ptr()).data.header), header);
+            let dst = (*inner.as_ptr()).data.slice.as_mut_ptr();
+            ptr::copy_nonoverlapping(items.as_ptr(), dst, items.len());
+
+            // Safety: ptr is valid & the inner structure is fully initialized
+            Rc::from_raw_inner(inner.as_ptr())
+        }
+    }
+
+    /// Creates an Rc for a HeaderSlice using the given header struct and
+    /// vec to generate the slice. The resulting Rc will be fat.
+    pub fn from_header_and_vec(header: H, mut v: Vec<T>) -> Self {
+        let len = v.len();
+        let inner = Rc::<_, S>::allocate_for_header_and_slice(len);
+
+        unsafe {
+            ptr::write(addr_of_mut!((*inner.as_ptr()).data.header), header);
+
+            // Safety:
+            // - `src` is valid for reads for `len` (got from `Vec`)
+            // - `dst` is valid for writes for `len` (just allocated, with layout for appropriate slice)
+            // - `src` and `dst` don't overlap (separate allocations)
+            let dst = addr_of_mut!((*inner.as_ptr()).data.slice) as *mut T;
+            ptr::copy_nonoverlapping(v.as_ptr(), dst, len);
+
+            // Deallocate vec without dropping `T`
+            v.set_len(0);
+
+            // Safety: ptr is valid & the inner structure is fully initialized
+            Rc::from_raw_inner(inner.as_ptr())
+        }
+    }
+}
+
+impl<H, S> Rc<HeaderSlice<H, str>, S>
+where
+    S: BackdropStrategy<Box<RcInner<HeaderSlice<H, str>>>>,
+{
+    /// Creates an Rc for a HeaderSlice using the given header struct and
+    /// a str slice to generate the slice. The resulting Rc will be fat.
+    pub fn from_header_and_str(header: H, string: &str) -> Self {
+        let bytes: Rc<HeaderSlice<H, [u8]>, backdrop::TrivialStrategy> =
+            Rc::from_header_and_slice(header, string.as_bytes());
+
+        // Safety: `RcInner` and `HeaderSlice` are `repr(C)`, `str` has the same layout as `[u8]`,
+        //         and `bytes` are a valid string since we've just got them from a valid `str`.
+        unsafe { Rc::from_raw_inner(Rc::into_raw_inner(bytes) as _) }
+    }
+}
+
+impl<T: ?Sized, S> From<Rc<HeaderSlice<(), T>, S>> for Rc<T, S>
+where
+    S: BackdropStrategy<Box<RcInner<HeaderSlice<(), T>>>>,
+    S: BackdropStrategy<Box<RcInner<T>>>,
+{
+    fn from(this: Rc<HeaderSlice<(), T>, S>) -> Self {
+        // Safety: `HeaderSlice<(), T>` and `T` has the same layout
+        unsafe { Rc::from_raw_inner(Rc::into_raw_inner(this) as _) }
+    }
+}
+
+impl<T: ?Sized, S> From<Rc<T, S>> for Rc<HeaderSlice<(), T>, S>
+where
+    S: BackdropStrategy<Box<RcInner<HeaderSlice<(), T>>>>,
+    S: BackdropStrategy<Box<RcInner<T>>>,
+{
+    fn from(this: Rc<T, S>) -> Self {
+        // Safety: `T` and `HeaderSlice<(), T>` has the same layout
+        unsafe { Rc::from_raw_inner(Rc::into_raw_inner(this) as _) }
+    }
+}
+
+impl<T: Copy, S> From<&[T]> for Rc<[T], S>
+where
+    S: BackdropStrategy<Box<RcInner<[T]>>>,
+    S: BackdropStrategy<Box<RcInner<HeaderSlice<(), [T]>>>>,
+{
+    fn from(slice: &[T]) -> Self {
+        Rc::from_header_and_slice((), slice).into()
+    }
+}
+
+impl<S> From<&str> for Rc<str, S>
+where
+    S: BackdropStrategy<Box<RcInner<str>>>,
+    S: BackdropStrategy<Box<RcInner<HeaderSlice<(), str>>>>,
+{
+    fn from(s: &str) -> Self {
+        Rc::from_header_and_str((), s).into()
+    }
+}
+
+impl<S> From<String> for Rc<str, S>
+where
+    S: BackdropStrategy<Box<RcInner<str>>>,
+    S: BackdropStrategy<Box<RcInner<HeaderSlice<(), str>>>>,
+{
+    fn from(s: String) -> Self {
+        Self::from(&s[..])
+    }
+}
+
+impl<T, S> From<Vec<T>> for Rc<[T], S>
+where
+    S: BackdropStrategy<Box<RcInner<[T]>>>,
+    S: BackdropStrategy<Box<RcInner<HeaderSlice<(), [T]>>>>,
+{
+    fn from(v: Vec<T>) -> Self {
+        Rc::from_header_and_vec((), v).into()
+    }
+}
+
+/// Moves a boxed (possibly unsized) value into a new `Rc`.
+impl<T: ?Sized, S> From<alloc::boxed::Box<T>> for Rc<T, S>
+where
+    S: BackdropStrategy<Box<RcInner<T>>>,
+{
+    fn from(b: alloc::boxed::Box<T>) -> Self {
+        let layout = Layout::for_value::<T>(&b);
+        let src = alloc::boxed::Box::into_raw(b);
+
+        // Safety: the closure only changes the address of the pointer, keeping the metadata of `src`
+        let inner = unsafe {
+            Self::allocate_for_layout(layout, |mem| set_data_ptr(src as *mut RcInner<T>, mem))
+        };
+
+        unsafe {
+            // Safety: see `From<Box<T>> for Arc<T, S>`
+            let dst = addr_of_mut!((*inner.as_ptr()).data) as *mut u8;
+            ptr::copy_nonoverlapping(src as *const u8, dst, layout.size());
+
+            // Deallocate box without dropping `T`
+            if layout.size() != 0 {
+                alloc::alloc::dealloc(src as *mut u8, layout);
+            }
+
+            // Safety: ptr is valid & the inner structure is fully initialized
+            Rc::from_raw_inner(inner.as_ptr())
+        }
+    }
+}
+
+impl<T, S: BackdropStrategy<Box<RcInner<T>>>> From<T> for Rc<T, S> {
+    #[inline]
+    fn from(t: T) -> Self {
+        Rc::new(t)
+    }
+}
+
+impl<A, S> FromIterator<A> for Rc<[A], S>
+where
+    S: BackdropStrategy<Box<RcInner<HeaderSlice<(), [A]>>>>,
+    S: BackdropStrategy<Box<RcInner<[A]>>>,
+{
+    fn from_iter<T: IntoIterator<Item = A>>(iter: T) -> Self {
+        let iter = iter.into_iter();
+        let (lower, upper) = iter.size_hint();
+        if Some(lower) == upper {
+            let iter = IteratorAsExactSizeIterator::new(iter);
+            Rc::from_header_and_iter((), iter).into()
+        } else {
+            iter.collect::<Vec<_>>().into()
+        }
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> Deref for Rc<T, S> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.inner().data
+    }
+}
+
+impl<T: ?Sized + PartialEq, S: BackdropStrategy<Box<RcInner<T>>>> PartialEq for Rc<T, S> {
+    fn eq(&self, other: &Rc<T, S>) -> bool {
+        Self::ptr_eq(self, other) || *(*self) == *(*other)
+    }
+}
+
+impl<T: ?Sized + Eq, S: BackdropStrategy<Box<RcInner<T>>>> Eq for Rc<T, S> {}
+
+impl<T: ?Sized + PartialOrd, S: BackdropStrategy<Box<RcInner<T>>>> PartialOrd for Rc<T, S> {
+    fn partial_cmp(&self, other: &Rc<T, S>) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Ord, S: BackdropStrategy<Box<RcInner<T>>>> Ord for Rc<T, S> {
+    fn cmp(&self, other: &Rc<T, S>) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Hash, S: BackdropStrategy<Box<RcInner<T>>>> Hash for Rc<T, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<T: ?Sized + fmt::Display, S: BackdropStrategy<Box<RcInner<T>>>> fmt::Display for Rc<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, S: BackdropStrategy<Box<RcInner<T>>>> fmt::Debug for Rc<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> fmt::Pointer for Rc<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.ptr(), f)
+    }
+}
+
+impl<T: Default, S: BackdropStrategy<Box<RcInner<T>>>> Default for Rc<T, S> {
+    #[inline]
+    fn default() -> Rc<T, S> {
+        Rc::new(Default::default())
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> borrow::Borrow<T> for Rc<T, S> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> AsRef<T> for Rc<T, S> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::{LeakStrategy, TrivialStrategy};
+    use crate::{HeaderSlice, Rc, UniqueRc};
+    use alloc::boxed::Box;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::cell::Cell;
+    use core::fmt::Display;
+
+    struct Counted<'a>(&'a Cell<usize>);
+
+    impl<'a> Drop for Counted<'a> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn try_unwrap() {
+        let x = Rc::<_, TrivialStrategy>::new(100usize);
+        let y = x.clone();
+
+        assert_eq!(Rc::count(&x), 2);
+        assert!(Rc::try_unwrap(x).is_err());
+
+        assert_eq!(Rc::count(&y), 1);
+        assert_eq!(Rc::try_unwrap(y), Ok(100));
+    }
+
+    #[test]
+    fn drop_runs_strategy() {
+        let drops = Cell::new(0);
+
+        let rc = Rc::<_, TrivialStrategy>::new(Counted(&drops));
+        let clones: Vec<_> = Rc::clone_many(&rc, 10).collect();
+        assert_eq!(Rc::count(&rc), 11);
+        drop(clones);
+        drop(rc);
+        assert_eq!(drops.get(), 1);
+
+        let leaked = Rc::<_, LeakStrategy>::new(Counted(&drops));
+        drop(leaked);
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    fn clone_many_partially_consumed() {
+        let rc = Rc::<_, TrivialStrategy>::new(1u8);
+        let mut iter = Rc::clone_many(&rc, 5);
+        let first = iter.next().unwrap();
+        assert_eq!(Rc::count(&rc), 6);
+        drop(iter);
+        assert_eq!(Rc::count(&rc), 2);
+        drop(first);
+        assert!(rc.is_unique());
+    }
+
+    #[test]
+    fn clone_many_cloned_iter() {
+        let rc = Rc::<_, TrivialStrategy>::new(1u8);
+        let mut iter = Rc::clone_many(&rc, 5);
+        let first = iter.next().unwrap();
+        let cloned = iter.clone();
+        assert_eq!(Rc::count(&rc), 10);
+        let second = cloned.clone().next().unwrap();
+        drop(iter);
+        drop(cloned);
+        assert_eq!(Rc::count(&rc), 3);
+        drop((first, second));
+        assert!(rc.is_unique());
+    }
+
+    #[test]
+    fn make_mut_and_unique() {
+        let mut a = Rc::<_, TrivialStrategy>::new(String::from("a"));
+        let b = a.clone();
+        Rc::make_mut(&mut a).push('!');
+        assert_eq!(*a, "a!");
+        assert_eq!(*b, "a");
+        assert!(Rc::ptr_eq(&a, &a.clone()));
+
+        let unique = Rc::try_unique(b).ok().unwrap();
+        assert_eq!(UniqueRc::into_inner(unique), "a");
+
+        let mut c = a.clone();
+        Rc::make_unique(&mut c).push('?');
+        assert!(!Rc::ptr_eq(&a, &c));
+        assert_eq!(*c, "a!?");
+    }
+
+    #[test]
+    fn header_slice() {
+        let rc = Rc::<_, TrivialStrategy>::from_header_and_iter(
+            42u8,
+            vec![String::from("1"), String::from("2")].into_iter(),
+        );
+        assert_eq!(rc.header, 42);
+        assert_eq!(rc.slice, [String::from("1"), String::from("2")]);
+
+        let rc: Rc<HeaderSlice<(), [u16]>, TrivialStrategy> =
+            Rc::from_header_and_slice((), &[1, 2, 3]);
+        let rc: Rc<[u16], TrivialStrategy> = rc.into();
+        assert_eq!(*rc, [1, 2, 3]);
+
+        let rc: Rc<str, TrivialStrategy> = Rc::from("str");
+        assert_eq!(&*rc, "str");
+
+        let rc: Rc<[String], TrivialStrategy> = Rc::from(vec![String::from("x")]);
+        assert_eq!(*rc, [String::from("x")]);
+
+        let rc: Rc<[u32], TrivialStrategy> = (0..4).filter(|x| x % 2 == 0).collect();
+        assert_eq!(*rc, [0, 2]);
+
+        let rc: Rc<dyn Display, TrivialStrategy> = Rc::from(Box::new(5) as Box<dyn Display>);
+        assert_eq!(rc.to_string(), "5");
+    }
+}
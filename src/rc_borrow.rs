@@ -0,0 +1,90 @@
+use allocator_api2::boxed::Box;
+use core::mem;
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
+use core::ptr;
+
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+use super::{Rc, RcInner};
+
+/// A "borrowed `Rc`". This is a pointer to
+/// a T that is known to have been allocated within an
+/// `Rc`.
+///
+/// The single-threaded counterpart of [`ArcBorrow`](crate::ArcBorrow):
+/// equivalent in guarantees to `&Rc<T, S>`, but in memory it's simply `&T`.
+#[derive(Debug, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct RcBorrow<'a, T: ?Sized + 'a>(pub(crate) &'a T);
+
+impl<'a, T> Copy for RcBorrow<'a, T> {}
+impl<'a, T> Clone for RcBorrow<'a, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> RcBorrow<'a, T> {
+    /// Clone this as an `Rc<T, S>`. This bumps the refcount.
+    #[inline]
+    pub fn clone_rc<S>(&self) -> Rc<T, S>
+    where
+        S: BackdropStrategy<Box<RcInner<T>>>,
+    {
+        let rc = unsafe { Rc::from_raw(self.0) };
+        // addref it!
+        mem::forget(rc.clone());
+        rc
+    }
+
+    /// For constructing from a reference known to be Rc-backed.
+    ///
+    /// # Safety
+    ///
+    /// `r` must point to the data of a live `RcInner<T>`.
+    #[inline]
+    pub unsafe fn from_ref(r: &'a T) -> Self {
+        RcBorrow(r)
+    }
+
+    /// Compare two `RcBorrow`s via pointer equality. Will only return
+    /// true if they come from the same allocation
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        ptr::eq(this.0 as *const T, other.0 as *const T)
+    }
+
+    /// Temporarily converts |self| into a bonafide Rc and exposes it to the
+    /// provided callback. The refcount is not modified.
+    #[inline]
+    pub fn with_rc<F, U, S>(&self, f: F) -> U
+    where
+        F: FnOnce(&Rc<T, S>) -> U,
+        T: 'static,
+        S: BackdropStrategy<Box<RcInner<T>>>,
+    {
+        // Synthesize transient Rc, which never touches the refcount.
+        let transient = unsafe { ManuallyDrop::new(Rc::from_raw(self.0)) };
+
+        f(&transient)
+    }
+
+    /// Similar to deref, but uses the lifetime |a| rather than the lifetime of
+    /// self, which is incompatible with the signature of the Deref trait.
+    #[inline]
+    pub fn get(&self) -> &'a T {
+        self.0
+    }
+}
+
+impl<'a, T> Deref for RcBorrow<'a, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.0
+    }
+}
@@ -0,0 +1,363 @@
+//! (De)serializing [`Arc`]s while preserving which of them share an allocation.
+//!
+//! Plain serde writes out the contents of an `Arc` every time it occurs,
+//! and deserializing creates a separate `Arc` for each occurrence.
+//! For structures in which the same `Arc` occurs in many places, that is both large and lossy.
+//!
+//! Fields using `#[serde(with = "backdrop_arc::serde_shared")]` (or wrapped in [`Shared`])
+//! instead write the contents of each `Arc` allocation only once, identified by [`Arc::heap_ptr`].
+//! Later occurrences only refer to it by a numeric id,
+//! and deserializing them returns clones of the same `Arc`.
+//!
+//! This requires the (de)serialization to run inside a [`scope`],
+//! which keeps track of the `Arc`s seen so far (on the current thread).
+//! Ids are only meaningful within the scope, so data serialized in one scope
+//! must be deserialized in one scope as well.
+//!
+//! ```
+//! # extern crate serde_test;
+//! use backdrop_arc::serde_shared::{self, Shared};
+//! use backdrop_arc::{Arc, TrivialStrategy};
+//! use serde_test::{assert_ser_tokens, Token};
+//!
+//! let a: Arc<str, TrivialStrategy> = Arc::from("a");
+//! let b: Arc<str, TrivialStrategy> = Arc::from("b");
+//! let path: Vec<Shared<str, TrivialStrategy>> = vec![a.clone().into(), b.into(), a.into()];
+//!
+//! // The contents of `a` are only written the first time; afterwards it is referred to by id `0`
+//! serde_shared::scope(|| {
+//!     assert_ser_tokens(&path, &[
+//!         Token::Seq { len: Some(3) },
+//!         Token::Tuple { len: 2 }, Token::U64(0), Token::Some, Token::Str("a"), Token::TupleEnd,
+//!         Token::Tuple { len: 2 }, Token::U64(1), Token::Some, Token::Str("b"), Token::TupleEnd,
+//!         Token::Tuple { len: 2 }, Token::U64(0), Token::None, Token::TupleEnd,
+//!         Token::SeqEnd,
+//!     ])
+//! });
+//! ```
+//!
+//! With serde's derive macros (and a format such as `serde_json`), individual fields opt in like this:
+//!
+//! ```ignore
+//! use backdrop_arc::{serde_shared, Arc, TrivialStrategy};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Edge {
+//!     #[serde(with = "backdrop_arc::serde_shared")]
+//!     from: Arc<str, TrivialStrategy>,
+//!     #[serde(with = "backdrop_arc::serde_shared")]
+//!     to: Arc<str, TrivialStrategy>,
+//! }
+//!
+//! fn roundtrip(edges: &[Edge]) -> serde_json::Result<Vec<Edge>> {
+//!     let json = serde_shared::scope(|| serde_json::to_string(edges))?;
+//!     serde_shared::scope(|| serde_json::from_str(&json))
+//! }
+//! ```
+
+use allocator_api2::boxed::Box;
+use core::any::Any;
+use core::cell::RefCell;
+use core::ffi::c_void;
+use core::fmt;
+use core::marker::PhantomData;
+use std::collections::HashMap;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{self, SerializeTuple, Serializer};
+use serde::{Deserialize, Serialize};
+
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+use super::{Arc, ArcInner};
+
+/// A type-erased clone of an `Arc<T, S>`.
+type AnyArc = alloc::boxed::Box<dyn Any>;
+
+#[derive(Default)]
+struct Context {
+    /// The id of every `Arc` serialized so far.
+    /// Also holds a clone of each, so its address can't be reused while the scope is active.
+    written: HashMap<*const c_void, (u64, AnyArc)>,
+    /// Every `Arc` deserialized so far, by id.
+    read: HashMap<u64, AnyArc>,
+}
+
+std::thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` in a scope in which `Arc`s (de)serialized using this module
+/// are only written once, and share their allocation again when read.
+///
+/// Nested scopes share the outermost scope.
+pub fn scope<R, F: FnOnce() -> R>(f: F) -> R {
+    let outermost = CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        let outermost = context.is_none();
+        if outermost {
+            *context = Some(Context::default());
+        }
+        outermost
+    });
+    if !outermost {
+        return f();
+    }
+
+    /// Ends the scope, even if `f` panics.
+    struct ScopeGuard;
+    impl Drop for ScopeGuard {
+        fn drop(&mut self) {
+            // Drop the `Arc`s outside of the borrow, since dropping them may run arbitrary code
+            let context = CONTEXT.with(|context| context.borrow_mut().take());
+            drop(context);
+        }
+    }
+    let _guard = ScopeGuard;
+    f()
+}
+
+fn with_context<R>(f: impl FnOnce(&mut Context) -> R) -> Option<R> {
+    CONTEXT.with(|context| context.borrow_mut().as_mut().map(f))
+}
+
+const NO_SCOPE: &str = "backdrop_arc::serde_shared used outside of `serde_shared::scope`";
+
+/// Serializes an `Arc` as a pair of an id and, the first time it occurs in the current [`scope`], its contents.
+pub fn serialize<T, S, Ser>(arc: &Arc<T, S>, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+where
+    T: ?Sized + Serialize + 'static,
+    S: BackdropStrategy<Box<ArcInner<T>>> + 'static,
+    Ser: Serializer,
+{
+    let (id, first) = with_context(|context| {
+        let next_id = context.written.len() as u64;
+        match context.written.get(&arc.heap_ptr()) {
+            Some(&(id, _)) => (id, false),
+            None => {
+                let clone: AnyArc = alloc::boxed::Box::new(arc.clone());
+                context.written.insert(arc.heap_ptr(), (next_id, clone));
+                (next_id, true)
+            }
+        }
+    })
+    .ok_or_else(|| ser::Error::custom(NO_SCOPE))?;
+
+    let mut tuple = serializer.serialize_tuple(2)?;
+    tuple.serialize_element(&id)?;
+    tuple.serialize_element(&if first { Some(&**arc) } else { None })?;
+    tuple.end()
+}
+
+/// Deserializes an `Arc` written by [`serialize`],
+/// returning a clone of the `Arc` with the same id if it was already read in the current [`scope`].
+pub fn deserialize<'de, T, S, D>(deserializer: D) -> Result<Arc<T, S>, D::Error>
+where
+    T: ?Sized + 'static,
+    S: BackdropStrategy<Box<ArcInner<T>>> + 'static,
+    Arc<T, S>: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_tuple(2, SharedVisitor(PhantomData, PhantomData))
+}
+
+struct SharedVisitor<T: ?Sized, S>(PhantomData<fn() -> *const T>, PhantomData<fn() -> S>);
+
+impl<'de, T, S> Visitor<'de> for SharedVisitor<T, S>
+where
+    T: ?Sized + 'static,
+    S: BackdropStrategy<Box<ArcInner<T>>> + 'static,
+    Arc<T, S>: Deserialize<'de>,
+{
+    type Value = Arc<T, S>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a shared Arc")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let id: u64 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let value: Option<Arc<T, S>> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        with_context(|context| match value {
+            Some(arc) => {
+                context.read.insert(id, alloc::boxed::Box::new(arc.clone()));
+                Ok(arc)
+            }
+            None => match context.read.get(&id) {
+                Some(any) => any.downcast_ref::<Arc<T, S>>().cloned().ok_or_else(|| {
+                    de::Error::custom(format_args!("shared Arc {} has a different type", id))
+                }),
+                None => Err(de::Error::custom(format_args!(
+                    "shared Arc {} was not read before",
+                    id
+                ))),
+            },
+        })
+        .unwrap_or_else(|| Err(de::Error::custom(NO_SCOPE)))
+    }
+}
+
+/// An `Arc` which is (de)serialized using this module.
+///
+/// Unlike `#[serde(with = "backdrop_arc::serde_shared")]`, this can also be used inside
+/// other types, e.g. `Vec<Shared<T, S>>` or `Option<Shared<T, S>>`.
+pub struct Shared<T: ?Sized, S>(pub Arc<T, S>)
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>;
+
+impl<T: ?Sized, S> From<Arc<T, S>> for Shared<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    #[inline]
+    fn from(arc: Arc<T, S>) -> Self {
+        Shared(arc)
+    }
+}
+
+impl<T: ?Sized, S> Clone for Shared<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        Shared(self.0.clone())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, S> fmt::Debug for Shared<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Shared").field(&self.0).finish()
+    }
+}
+
+impl<T, S> Serialize for Shared<T, S>
+where
+    T: ?Sized + Serialize + 'static,
+    S: BackdropStrategy<Box<ArcInner<T>>> + 'static,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T, S> Deserialize<'de> for Shared<T, S>
+where
+    T: ?Sized + 'static,
+    S: BackdropStrategy<Box<ArcInner<T>>> + 'static,
+    Arc<T, S>: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize(deserializer).map(Shared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::TrivialStrategy;
+    use super::{scope, Shared};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::fmt;
+    use serde::{Deserialize, Deserializer};
+    use serde_test::{
+        assert_de_tokens, assert_de_tokens_error, assert_ser_tokens, assert_ser_tokens_error, Token,
+    };
+
+    use crate::Arc;
+
+    /// Equal if both the contents and which elements share an `Arc` are equal.
+    struct Sharing(Vec<Shared<[u8], TrivialStrategy>>);
+
+    impl<'de> Deserialize<'de> for Sharing {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Vec::deserialize(deserializer).map(Sharing)
+        }
+    }
+
+    impl PartialEq for Sharing {
+        fn eq(&self, other: &Sharing) -> bool {
+            let sharing = |list: &[Shared<[u8], TrivialStrategy>]| -> Vec<bool> {
+                list.iter()
+                    .flat_map(|a| list.iter().map(move |b| Arc::ptr_eq(&a.0, &b.0)))
+                    .collect()
+            };
+            self.0
+                .iter()
+                .map(|s| &*s.0)
+                .eq(other.0.iter().map(|s| &*s.0))
+                && sharing(&self.0) == sharing(&other.0)
+        }
+    }
+
+    impl fmt::Debug for Sharing {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_list().entries(&self.0).finish()
+        }
+    }
+
+    #[test]
+    fn writes_each_arc_once() {
+        let shared: Arc<[u8], TrivialStrategy> = Arc::from(&[1, 2][..]);
+        let other: Arc<[u8], TrivialStrategy> = Arc::from(&[1, 2][..]);
+        let list = Sharing(vec![Shared(shared.clone()), Shared(other), Shared(shared)]);
+        let tokens = [
+            Token::Seq { len: Some(3) },
+            Token::Tuple { len: 2 },
+            Token::U64(0),
+            Token::Some,
+            Token::Seq { len: Some(2) },
+            Token::U8(1),
+            Token::U8(2),
+            Token::SeqEnd,
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::U64(1),
+            Token::Some,
+            Token::Seq { len: Some(2) },
+            Token::U8(1),
+            Token::U8(2),
+            Token::SeqEnd,
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::U64(0),
+            Token::None,
+            Token::TupleEnd,
+            Token::SeqEnd,
+        ];
+        scope(|| assert_ser_tokens(&list.0, &tokens));
+        scope(|| assert_de_tokens(&list, &tokens));
+    }
+
+    #[test]
+    fn errors() {
+        let arc: Arc<u8, TrivialStrategy> = Arc::new(1);
+        assert_ser_tokens_error(&Shared(arc), &[], super::NO_SCOPE);
+
+        scope(|| {
+            assert_de_tokens_error::<Shared<u8, TrivialStrategy>>(
+                &[Token::Tuple { len: 2 }, Token::U64(3), Token::None],
+                "shared Arc 3 was not read before",
+            )
+        });
+    }
+}
@@ -0,0 +1,432 @@
+use alloc::vec::Vec;
+use allocator_api2::boxed::Box;
+use core::cmp;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::str;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+use super::{Arc, ArcInner, ArcSliceBuilder, ArcStrBuilder, HeaderSlice, HeaderWithLength};
+
+/// Caps the number of elements to reserve up front based on a size hint from the input,
+/// so that (malicious) input can't make us allocate a lot of memory before any elements were read.
+fn cautious_capacity<T>(hint: Option<usize>) -> usize {
+    const MAX_PREALLOC_BYTES: usize = 1024 * 1024;
+    match mem::size_of::<T>() {
+        0 => 0,
+        size => cmp::min(hint.unwrap_or(0), MAX_PREALLOC_BYTES / size),
+    }
+}
+
+/// Deserializes a sequence by pushing its elements into an `ArcSliceBuilder`.
+///
+/// When the input knows the length of the sequence up front,
+/// the elements are written straight into an allocation of the final size.
+struct ExtendBuilder<H, T, S>(ArcSliceBuilder<H, T, S>)
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>;
+
+impl<'de, H, T: Deserialize<'de>, S> DeserializeSeed<'de> for ExtendBuilder<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    type Value = ArcSliceBuilder<H, T, S>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, H, T: Deserialize<'de>, S> Visitor<'de> for ExtendBuilder<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    type Value = ArcSliceBuilder<H, T, S>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut builder = self.0;
+        builder.reserve(cautious_capacity::<T>(seq.size_hint()));
+        while let Some(item) = seq.next_element()? {
+            builder.push(item);
+        }
+        Ok(builder)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, S> Deserialize<'de> for Arc<[T], S>
+where
+    S: BackdropStrategy<Box<ArcInner<[T]>>>,
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<(), [T]>>>>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Arc<[T], S>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let builder = ExtendBuilder(ArcSliceBuilder::new(())).deserialize(deserializer)?;
+        Ok(builder.finish_slice().shareable())
+    }
+}
+
+struct StrVisitor<S>(PhantomData<fn() -> S>);
+
+impl<'de, S> Visitor<'de> for StrVisitor<S>
+where
+    S: BackdropStrategy<Box<ArcInner<str>>>,
+{
+    type Value = Arc<str, S>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, string: &str) -> Result<Self::Value, E> {
+        let mut builder = ArcStrBuilder::with_capacity((), string.len());
+        builder.push_str(string);
+        Ok(builder.finish_str().shareable())
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        match str::from_utf8(bytes) {
+            Ok(string) => self.visit_str(string),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Bytes(bytes), &self)),
+        }
+    }
+}
+
+impl<'de, S> Deserialize<'de> for Arc<str, S>
+where
+    S: BackdropStrategy<Box<ArcInner<str>>>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Arc<str, S>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(StrVisitor(PhantomData))
+    }
+}
+
+/// Serialized as a struct with the fields `header` and `slice`.
+impl<H: Serialize, T: ?Sized + Serialize> Serialize for HeaderSlice<H, T> {
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let mut state = serializer.serialize_struct("HeaderSlice", 2)?;
+        state.serialize_field("header", &self.header)?;
+        state.serialize_field("slice", &self.slice)?;
+        state.end()
+    }
+}
+
+const FIELDS: &[&str] = &["header", "slice"];
+
+enum Field {
+    Header,
+    Slice,
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FieldVisitor;
+
+        impl<'de> Visitor<'de> for FieldVisitor {
+            type Value = Field;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("`header` or `slice`")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Field, E> {
+                match value {
+                    "header" => Ok(Field::Header),
+                    "slice" => Ok(Field::Slice),
+                    _ => Err(E::unknown_field(value, FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+
+struct HeaderSliceVisitor<H, T, S>(PhantomData<(H, T)>, PhantomData<fn() -> S>);
+
+impl<'de, H, T, S> Visitor<'de> for HeaderSliceVisitor<H, T, S>
+where
+    H: Deserialize<'de>,
+    T: Deserialize<'de>,
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    type Value = Arc<HeaderSlice<H, [T]>, S>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct HeaderSlice")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let header = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let builder = seq
+            .next_element_seed(ExtendBuilder(ArcSliceBuilder::new(header)))?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok(builder.finish().shareable())
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut header = None;
+        let mut builder = None;
+        // Only used if the slice comes before the header
+        let mut items = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                Field::Header => {
+                    if header.is_some() || builder.is_some() {
+                        return Err(de::Error::duplicate_field("header"));
+                    }
+                    header = Some(map.next_value()?);
+                }
+                Field::Slice => {
+                    if builder.is_some() || items.is_some() {
+                        return Err(de::Error::duplicate_field("slice"));
+                    }
+                    match header.take() {
+                        Some(header) => {
+                            let seed = ExtendBuilder(ArcSliceBuilder::new(header));
+                            builder = Some(map.next_value_seed(seed)?);
+                        }
+                        None => items = Some(map.next_value::<Vec<T>>()?),
+                    }
+                }
+            }
+        }
+
+        match (builder, header, items) {
+            (Some(builder), _, _) => Ok(builder.finish().shareable()),
+            (None, Some(header), Some(items)) => Ok(Arc::from_header_and_vec(header, items)),
+            (None, None, _) => Err(de::Error::missing_field("header")),
+            (None, Some(_), None) => Err(de::Error::missing_field("slice")),
+        }
+    }
+}
+
+impl<'de, H, T, S> Deserialize<'de> for Arc<HeaderSlice<H, [T]>, S>
+where
+    H: Deserialize<'de>,
+    T: Deserialize<'de>,
+    S: BackdropStrategy<Box<ArcInner<HeaderSlice<H, [T]>>>>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            "HeaderSlice",
+            FIELDS,
+            HeaderSliceVisitor(PhantomData, PhantomData),
+        )
+    }
+}
+
+/// Serialized as just its `header`, since the length is implied by the slice that follows it.
+impl<H: Serialize> Serialize for HeaderWithLength<H> {
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        self.header.serialize(serializer)
+    }
+}
+
+/// Deserializes a `HeaderSlice` (as serialized above) into its header and a `Vec` of its items,
+/// for when the length has to be known before the allocation is made.
+pub(crate) fn deserialize_header_and_items<'de, D, H, T>(
+    deserializer: D,
+) -> Result<(H, Vec<T>), D::Error>
+where
+    D: Deserializer<'de>,
+    H: Deserialize<'de>,
+    T: Deserialize<'de>,
+{
+    deserializer.deserialize_struct("HeaderSlice", FIELDS, HeaderAndItemsVisitor(PhantomData))
+}
+
+struct HeaderAndItemsVisitor<H, T>(PhantomData<(H, T)>);
+
+impl<'de, H, T> Visitor<'de> for HeaderAndItemsVisitor<H, T>
+where
+    H: Deserialize<'de>,
+    T: Deserialize<'de>,
+{
+    type Value = (H, Vec<T>);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct HeaderSlice")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let header = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let items = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok((header, items))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut header = None;
+        let mut items = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                Field::Header if header.is_some() => {
+                    return Err(de::Error::duplicate_field("header"))
+                }
+                Field::Header => header = Some(map.next_value()?),
+                Field::Slice if items.is_some() => return Err(de::Error::duplicate_field("slice")),
+                Field::Slice => items = Some(map.next_value()?),
+            }
+        }
+        let header = header.ok_or_else(|| de::Error::missing_field("header"))?;
+        let items = items.ok_or_else(|| de::Error::missing_field("slice"))?;
+        Ok((header, items))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::TrivialStrategy;
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use serde::{Deserialize, Deserializer};
+    use serde_test::{assert_de_tokens, assert_ser_tokens, assert_tokens, Token};
+
+    use crate::{Arc, HeaderSlice, UniqueArc};
+
+    #[test]
+    fn unsized_arcs() {
+        let slice: Arc<[u32], TrivialStrategy> = Arc::from(&[1, 2][..]);
+        let tokens = [
+            Token::Seq { len: Some(2) },
+            Token::U32(1),
+            Token::U32(2),
+            Token::SeqEnd,
+        ];
+        assert_tokens(&slice, &tokens);
+        // Without a known length
+        assert_de_tokens(
+            &slice,
+            &[
+                Token::Seq { len: None },
+                Token::U32(1),
+                Token::U32(2),
+                Token::SeqEnd,
+            ],
+        );
+
+        let string: Arc<str, TrivialStrategy> = Arc::from("hello");
+        assert_tokens(&string, &[Token::Str("hello")]);
+        assert_de_tokens(&string, &[Token::String("hello")]);
+        assert_de_tokens(&string, &[Token::Bytes(b"hello")]);
+
+        // `UniqueArc` itself has no `PartialEq` and `Debug` to compare with
+        #[derive(PartialEq, Debug)]
+        struct Unique(Vec<String>);
+        impl<'de> Deserialize<'de> for Unique {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let mut unique = UniqueArc::<[String], TrivialStrategy>::deserialize(deserializer)?;
+                unique[0].push('!');
+                Ok(Unique(unique.to_vec()))
+            }
+        }
+        let tokens = [Token::Seq { len: Some(1) }, Token::Str("a"), Token::SeqEnd];
+        let unique: UniqueArc<[String], TrivialStrategy> =
+            [String::from("a")].iter().cloned().collect();
+        assert_ser_tokens(&unique, &tokens);
+        assert_de_tokens(&Unique(vec![String::from("a!")]), &tokens);
+    }
+
+    #[test]
+    fn header_slice() {
+        let arc: Arc<HeaderSlice<u8, [u16]>, TrivialStrategy> =
+            Arc::from_header_and_slice(7, &[1, 2]);
+        assert_tokens(
+            &arc,
+            &[
+                Token::Struct {
+                    name: "HeaderSlice",
+                    len: 2,
+                },
+                Token::Str("header"),
+                Token::U8(7),
+                Token::Str("slice"),
+                Token::Seq { len: Some(2) },
+                Token::U16(1),
+                Token::U16(2),
+                Token::SeqEnd,
+                Token::StructEnd,
+            ],
+        );
+        // With the fields in a different order
+        assert_de_tokens(
+            &arc,
+            &[
+                Token::Map { len: None },
+                Token::Str("slice"),
+                Token::Seq { len: None },
+                Token::U16(1),
+                Token::U16(2),
+                Token::SeqEnd,
+                Token::Str("header"),
+                Token::U8(7),
+                Token::MapEnd,
+            ],
+        );
+        // As a tuple
+        assert_de_tokens(
+            &arc,
+            &[
+                Token::Tuple { len: 2 },
+                Token::U8(7),
+                Token::Seq { len: Some(2) },
+                Token::U16(1),
+                Token::U16(2),
+                Token::SeqEnd,
+                Token::TupleEnd,
+            ],
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use allocator_api2::boxed::Box;
 use core::ffi::c_void;
 use core::fmt;
 use core::hash::{Hash, Hasher};
@@ -6,9 +7,11 @@ use core::marker::PhantomData;
 use core::mem::ManuallyDrop;
 use core::ops::Deref;
 use core::ptr;
-use core::usize;
 
-use super::{Arc, ArcInner, HeaderSliceWithLength, HeaderWithLength};
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+use super::{Arc, ArcInner, HeaderSlice, HeaderSliceWithLength, HeaderWithLength};
 
 /// A "thin" `Arc` containing dynamically sized data
 ///
@@ -25,19 +28,47 @@ use super::{Arc, ArcInner, HeaderSliceWithLength, HeaderWithLength};
 ///
 /// `ThinArc` solves this by storing the length in the allocation itself,
 /// via `HeaderSliceWithLength`.
+///
+/// Just like [`Arc`], the backdrop strategy `S` is executed
+/// when the last `ThinArc` (or `Arc` it was converted from/to) goes out of scope.
+/// The strategy receives the (fat) `Box<ArcInner<HeaderSlice<HeaderWithLength<H>, [T]>>>`.
+///
+/// ```
+/// use backdrop_arc::{Arc, HeaderWithLength, ThinArc, TrivialStrategy};
+///
+/// let thin: ThinArc<&str, u32, TrivialStrategy> =
+///     ThinArc::from_header_and_slice("numbers", &[1, 2, 3]);
+/// assert_eq!(core::mem::size_of_val(&thin), core::mem::size_of::<usize>());
+/// assert_eq!(thin.header.header, "numbers");
+/// assert_eq!(thin.header.length, 3);
+/// assert_eq!(thin.slice, [1, 2, 3]);
+///
+/// let fat = Arc::from_thin(thin);
+/// assert_eq!(fat.slice, [1, 2, 3]);
+/// ```
 #[repr(transparent)]
-pub struct ThinArc<H, T> {
+pub struct ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
     ptr: ptr::NonNull<ArcInner<HeaderSliceWithLength<H, [T; 0]>>>,
     phantom: PhantomData<(H, T)>,
+    phantom_strategy: PhantomData<S>,
 }
 
-unsafe impl<H: Sync + Send, T: Sync + Send> Send for ThinArc<H, T> {}
-unsafe impl<H: Sync + Send, T: Sync + Send> Sync for ThinArc<H, T> {}
+unsafe impl<H: Sync + Send, T: Sync + Send, S> Send for ThinArc<H, T, S> where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>
+{
+}
+unsafe impl<H: Sync + Send, T: Sync + Send, S> Sync for ThinArc<H, T, S> where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>
+{
+}
 
 // Synthesize a fat pointer from a thin pointer.
 //
-// See the comment around the analogous operation in from_header_and_iter.
-fn thin_to_thick<H, T>(
+// See the comment around the analogous operation in `Arc::header_and_slice_ptr`.
+pub(crate) fn thin_to_thick<H, T>(
     thin: *mut ArcInner<HeaderSliceWithLength<H, [T; 0]>>,
 ) -> *mut ArcInner<HeaderSliceWithLength<H, [T]>> {
     let len = unsafe { (*thin).data.header.length };
@@ -46,21 +77,87 @@ fn thin_to_thick<H, T>(
     fake_slice as *mut ArcInner<HeaderSliceWithLength<H, [T]>>
 }
 
-impl<H, T> ThinArc<H, T> {
+/// A type whose `ArcInner` can be found from just its address,
+/// so that an `Arc` containing it can be stored as a thin pointer
+/// (for instance in an [`ArcUnion`](crate::ArcUnion)).
+///
+/// This is the case for all sized types, and for a `HeaderSlice` with a
+/// `HeaderWithLength`, whose length is read from its header (just like `ThinArc` does).
+///
+/// # Safety
+///
+/// `thicken` must return a pointer to the `ArcInner<Self>` at the given address,
+/// with the same metadata as the pointer it was allocated with.
+pub unsafe trait ThinPointee {
+    /// Reconstructs the (possibly fat) pointer to the `ArcInner<Self>` at `thin`.
+    ///
+    /// # Safety
+    ///
+    /// `thin` must point to a live `ArcInner<Self>`.
+    unsafe fn thicken(thin: *mut ()) -> *mut ArcInner<Self>;
+
+    /// Panics if `thicken` would not reconstruct the metadata of a pointer to `self`.
+    ///
+    /// This must be called before an `Arc<Self>` is stored as a thin pointer.
+    #[inline]
+    fn assert_thin(&self) {}
+
+    /// The offset of the data in an `ArcInner<Self>`,
+    /// which is used to go back from a pointer to the data (like the one in an
+    /// [`OffsetArc`](crate::OffsetArc)) to the `ArcInner`.
+    ///
+    /// This must not depend on the metadata of the pointer.
+    fn data_offset() -> usize;
+}
+
+unsafe impl<T> ThinPointee for T {
+    #[inline]
+    unsafe fn thicken(thin: *mut ()) -> *mut ArcInner<T> {
+        thin as *mut ArcInner<T>
+    }
+
+    #[inline]
+    fn data_offset() -> usize {
+        offset_of!(ArcInner<T>, data)
+    }
+}
+
+unsafe impl<H, T> ThinPointee for HeaderSlice<HeaderWithLength<H>, [T]> {
+    #[inline]
+    unsafe fn thicken(thin: *mut ()) -> *mut ArcInner<Self> {
+        thin_to_thick(thin as *mut ArcInner<HeaderSliceWithLength<H, [T; 0]>>)
+    }
+
+    #[inline]
+    fn assert_thin(&self) {
+        assert_eq!(
+            self.header.length,
+            self.slice.len(),
+            "Length needs to be correct for ThinArc to work"
+        );
+    }
+
+    #[inline]
+    fn data_offset() -> usize {
+        // `[T; 0]` has the same alignment as `[T]`, so the data starts at the same offset.
+        offset_of!(ArcInner<HeaderSliceWithLength<H, [T; 0]>>, data)
+    }
+}
+
+impl<H, T, S> ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
     /// Temporarily converts |self| into a bonafide Arc and exposes it to the
     /// provided callback. The refcount is not modified.
     #[inline]
     pub fn with_arc<F, U>(&self, f: F) -> U
     where
-        F: FnOnce(&Arc<HeaderSliceWithLength<H, [T]>>) -> U,
+        F: FnOnce(&Arc<HeaderSliceWithLength<H, [T]>, S>) -> U,
     {
         // Synthesize transient Arc, which never touches the refcount of the ArcInner.
-        let transient = unsafe {
-            ManuallyDrop::new(Arc {
-                p: ptr::NonNull::new_unchecked(thin_to_thick(self.ptr.as_ptr())),
-                phantom: PhantomData,
-            })
-        };
+        let transient =
+            unsafe { ManuallyDrop::new(Arc::from_raw_inner(thin_to_thick(self.ptr.as_ptr()))) };
 
         // Expose the transient Arc to the callback, which may clone it if it wants
         // and forward the result to the user
@@ -73,8 +170,7 @@ impl<H, T> ThinArc<H, T> {
     where
         I: Iterator<Item = T> + ExactSizeIterator,
     {
-        let header = HeaderWithLength::new(header, items.len());
-        Arc::into_thin(Arc::from_header_and_iter(header, items))
+        Arc::into_thin(Arc::<_, S>::from_header_with_length_and_iter(header, items))
     }
 
     /// Creates a `ThinArc` for a HeaderSlice using the given header struct and
@@ -84,7 +180,7 @@ impl<H, T> ThinArc<H, T> {
         T: Copy,
     {
         let header = HeaderWithLength::new(header, items.len());
-        Arc::into_thin(Arc::from_header_and_slice(header, items))
+        Arc::into_thin(Arc::<_, S>::from_header_and_slice(header, items))
     }
 
     /// Returns the address on the heap of the ThinArc itself -- not the T
@@ -117,6 +213,7 @@ impl<H, T> ThinArc<H, T> {
         Self {
             ptr: ptr::NonNull::new_unchecked(ptr as *mut c_void).cast(),
             phantom: PhantomData,
+            phantom_strategy: PhantomData,
         }
     }
 
@@ -136,7 +233,10 @@ impl<H, T> ThinArc<H, T> {
     }
 }
 
-impl<H, T> Deref for ThinArc<H, T> {
+impl<H, T, S> Deref for ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
     type Target = HeaderSliceWithLength<H, [T]>;
 
     #[inline]
@@ -145,28 +245,38 @@ impl<H, T> Deref for ThinArc<H, T> {
     }
 }
 
-impl<H, T> Clone for ThinArc<H, T> {
+impl<H, T, S> Clone for ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
     #[inline]
     fn clone(&self) -> Self {
         ThinArc::with_arc(self, |a| Arc::into_thin(a.clone()))
     }
 }
 
-impl<H, T> Drop for ThinArc<H, T> {
+impl<H, T, S> Drop for ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
     #[inline]
     fn drop(&mut self) {
-        let _ = Arc::from_thin(ThinArc {
+        let _ = Arc::from_thin(ThinArc::<H, T, S> {
             ptr: self.ptr,
             phantom: PhantomData,
+            phantom_strategy: PhantomData,
         });
     }
 }
 
-impl<H, T> Arc<HeaderSliceWithLength<H, [T]>> {
+impl<H, T, S> Arc<HeaderSliceWithLength<H, [T]>, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
     /// Converts an `Arc` into a `ThinArc`. This consumes the `Arc`, so the refcount
     /// is not modified.
     #[inline]
-    pub fn into_thin(a: Self) -> ThinArc<H, T> {
+    pub fn into_thin(a: Self) -> ThinArc<H, T, S> {
         let a = ManuallyDrop::new(a);
         assert_eq!(
             a.header.length,
@@ -182,46 +292,77 @@ impl<H, T> Arc<HeaderSliceWithLength<H, [T]>> {
                 )
             },
             phantom: PhantomData,
+            phantom_strategy: PhantomData,
         }
     }
 
     /// Converts a `ThinArc` into an `Arc`. This consumes the `ThinArc`, so the refcount
     /// is not modified.
     #[inline]
-    pub fn from_thin(a: ThinArc<H, T>) -> Self {
+    pub fn from_thin(a: ThinArc<H, T, S>) -> Self {
         let a = ManuallyDrop::new(a);
         let ptr = thin_to_thick(a.ptr.as_ptr());
-        unsafe {
-            Arc {
-                p: ptr::NonNull::new_unchecked(ptr),
-                phantom: PhantomData,
-            }
-        }
+        unsafe { Arc::from_raw_inner(ptr) }
     }
 }
 
-impl<H: PartialEq, T: PartialEq> PartialEq for ThinArc<H, T> {
+impl<H, T, S> From<Arc<HeaderSliceWithLength<H, [T]>, S>> for ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
     #[inline]
-    fn eq(&self, other: &ThinArc<H, T>) -> bool {
+    fn from(arc: Arc<HeaderSliceWithLength<H, [T]>, S>) -> Self {
+        Arc::into_thin(arc)
+    }
+}
+
+impl<H, T, S> From<ThinArc<H, T, S>> for Arc<HeaderSliceWithLength<H, [T]>, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
+    #[inline]
+    fn from(arc: ThinArc<H, T, S>) -> Self {
+        Arc::from_thin(arc)
+    }
+}
+
+impl<H: PartialEq, T: PartialEq, S> PartialEq for ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
+    #[inline]
+    fn eq(&self, other: &ThinArc<H, T, S>) -> bool {
         ThinArc::with_arc(self, |a| ThinArc::with_arc(other, |b| *a == *b))
     }
 }
 
-impl<H: Eq, T: Eq> Eq for ThinArc<H, T> {}
+impl<H: Eq, T: Eq, S> Eq for ThinArc<H, T, S> where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>
+{
+}
 
-impl<H: Hash, T: Hash> Hash for ThinArc<H, T> {
+impl<H: Hash, T: Hash, S> Hash for ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
     fn hash<HSR: Hasher>(&self, state: &mut HSR) {
         ThinArc::with_arc(self, |a| a.hash(state))
     }
 }
 
-impl<H: fmt::Debug, T: fmt::Debug> fmt::Debug for ThinArc<H, T> {
+impl<H: fmt::Debug, T: fmt::Debug, S> fmt::Debug for ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Debug::fmt(&**self, f)
     }
 }
 
-impl<H, T> fmt::Pointer for ThinArc<H, T> {
+impl<H, T, S> fmt::Pointer for ThinArc<H, T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<HeaderSliceWithLength<H, [T]>>>>,
+{
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Pointer::fmt(&self.ptr(), f)
     }
@@ -229,6 +370,7 @@ impl<H, T> fmt::Pointer for ThinArc<H, T> {
 
 #[cfg(test)]
 mod tests {
+    use super::backdrop::{LeakStrategy, TrivialStrategy};
     use crate::{Arc, HeaderWithLength, ThinArc};
     use alloc::vec;
     use core::clone::Clone;
@@ -250,7 +392,7 @@ mod tests {
     #[test]
     fn empty_thin() {
         let header = HeaderWithLength::new(100u32, 0);
-        let x = Arc::from_header_and_iter(header, core::iter::empty::<i32>());
+        let x = Arc::<_, TrivialStrategy>::from_header_and_iter(header, core::iter::empty::<i32>());
         let y = Arc::into_thin(x.clone());
         assert_eq!(y.header.header, 100);
         assert!(y.slice.is_empty());
@@ -269,7 +411,7 @@ mod tests {
         // The header will have more alignment than `Padded`
         let header = HeaderWithLength::new(0i32, 2);
         let items = vec![Padded { i: 0xdead }, Padded { i: 0xbeef }];
-        let a = ThinArc::from_header_and_iter(header, items.into_iter());
+        let a = ThinArc::<_, _, TrivialStrategy>::from_header_and_iter(header, items.into_iter());
         assert_eq!(a.slice.len(), 2);
         assert_eq!(a.slice[0].i, 0xdead);
         assert_eq!(a.slice[1].i, 0xbeef);
@@ -283,7 +425,7 @@ mod tests {
         let v = vec![5, 6];
         let header = HeaderWithLength::new(c, v.len());
         {
-            let x = Arc::into_thin(Arc::from_header_and_slice(header, &v));
+            let x = Arc::into_thin(Arc::<_, TrivialStrategy>::from_header_and_slice(header, &v));
             let y = ThinArc::with_arc(&x, |q| q.clone());
             let _ = y.clone();
             let _ = x == x;
@@ -300,7 +442,10 @@ mod tests {
         let v = vec![5, 6];
         let header = HeaderWithLength::new(c, v.len());
         {
-            let x = Arc::into_thin(Arc::from_header_and_iter(header, v.into_iter()));
+            let x = Arc::into_thin(Arc::<_, TrivialStrategy>::from_header_and_iter(
+                header,
+                v.into_iter(),
+            ));
             let y = ThinArc::with_arc(&x, |q| q.clone());
             let _ = y.clone();
             let _ = x == x;
@@ -316,7 +461,7 @@ mod tests {
         let v = vec![5, 6];
         let header = HeaderWithLength::new(c, v.len());
         {
-            type ThinArcCanary = ThinArc<Canary, u32>;
+            type ThinArcCanary = ThinArc<Canary, u32, TrivialStrategy>;
             let x: ThinArcCanary = Arc::into_thin(Arc::from_header_and_iter(header, v.into_iter()));
             let ptr = x.as_ptr();
 
@@ -326,4 +471,16 @@ mod tests {
         }
         assert_eq!(canary.load(Acquire), 1);
     }
+
+    #[test]
+    fn thin_drop_uses_strategy() {
+        let mut canary = atomic::AtomicUsize::new(0);
+        let c = Canary(&mut canary as *mut atomic::AtomicUsize);
+        {
+            let x = ThinArc::<_, _, LeakStrategy>::from_header_and_slice(c, &[1u8, 2, 3]);
+            let _y = x.clone();
+        }
+        // `LeakStrategy` never drops the contents
+        assert_eq!(canary.load(Acquire), 0);
+    }
 }
@@ -0,0 +1,183 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use allocator_api2::alloc::Allocator;
+use allocator_api2::boxed::Box;
+use core::marker::PhantomData;
+use core::mem;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+use super::{ArcInner, HeaderSlice};
+
+/// An estimate of how expensive it is to drop a value.
+///
+/// The cost is (roughly) the number of elements the value holds,
+/// which is what dropping it will have to visit or free.
+/// Sequences of elements without drop glue (such as `[u8]` or `str`)
+/// are freed in one go, so they cost `1` regardless of their length.
+/// Computing the estimate should be cheap (i.e. not traverse the value).
+///
+/// Used by [`ThresholdStrategy`] to decide whether dropping is worth moving to the background.
+pub trait DropCost {
+    /// The estimated cost of dropping `self`.
+    fn drop_cost(&self) -> usize;
+}
+
+/// The cost of dropping `len` contiguous `T`s: only elements with drop glue have to be visited.
+#[inline]
+fn slice_cost<T>(len: usize) -> usize {
+    if mem::needs_drop::<T>() {
+        len
+    } else {
+        1
+    }
+}
+
+impl<T> DropCost for [T] {
+    #[inline]
+    fn drop_cost(&self) -> usize {
+        slice_cost::<T>(self.len())
+    }
+}
+
+impl DropCost for str {
+    #[inline]
+    fn drop_cost(&self) -> usize {
+        1
+    }
+}
+
+impl<T> DropCost for Vec<T> {
+    #[inline]
+    fn drop_cost(&self) -> usize {
+        slice_cost::<T>(self.len())
+    }
+}
+
+impl DropCost for String {
+    #[inline]
+    fn drop_cost(&self) -> usize {
+        1
+    }
+}
+
+impl<K, V> DropCost for BTreeMap<K, V> {
+    #[inline]
+    fn drop_cost(&self) -> usize {
+        self.len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, H> DropCost for HashMap<K, V, H> {
+    #[inline]
+    fn drop_cost(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<H, T> DropCost for HeaderSlice<H, [T]> {
+    #[inline]
+    fn drop_cost(&self) -> usize {
+        self.slice.drop_cost()
+    }
+}
+
+/// A strategy which picks one of two strategies based on the [`DropCost`] of the value.
+///
+/// When the last clone of an `Arc` goes out of scope,
+/// values whose cost is at least `N` are dropped using `Heavy`,
+/// and all other values using `Inline`.
+///
+/// This way, small values can be dropped inline (which is cheaper than sending them elsewhere),
+/// and only large values are moved to e.g. a background thread.
+///
+/// ```
+/// use backdrop_arc::{Arc, LeakStrategy, ThresholdStrategy, TrivialStrategy};
+///
+/// type Strategy = ThresholdStrategy<TrivialStrategy, LeakStrategy, 1000>;
+///
+/// let small: Arc<Vec<u8>, Strategy> = Arc::new(vec![1, 2, 3]);
+/// let large: Arc<Vec<u8>, Strategy> = Arc::new(vec![0; 1000]);
+/// drop(small); // dropped inline, using `TrivialStrategy`
+/// drop(large); // leaked, using `LeakStrategy`
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ThresholdStrategy<Inline, Heavy, const N: usize>(PhantomData<(Inline, Heavy)>);
+
+impl<T, A, Inline, Heavy, const N: usize> BackdropStrategy<Box<ArcInner<T>, A>>
+    for ThresholdStrategy<Inline, Heavy, N>
+where
+    T: ?Sized + DropCost,
+    A: Allocator,
+    Inline: BackdropStrategy<Box<ArcInner<T>, A>>,
+    Heavy: BackdropStrategy<Box<ArcInner<T>, A>>,
+{
+    #[inline]
+    fn execute(droppable: Box<ArcInner<T>, A>) {
+        if droppable.data.drop_cost() >= N {
+            Heavy::execute(droppable)
+        } else {
+            Inline::execute(droppable)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backdrop::{LeakStrategy, TrivialStrategy};
+    use super::{DropCost, ThresholdStrategy};
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    use crate::{Arc, HeaderSlice};
+
+    struct Counted<'a>(&'a AtomicUsize);
+
+    impl<'a> Drop for Counted<'a> {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, SeqCst);
+        }
+    }
+
+    type Strategy = ThresholdStrategy<TrivialStrategy, LeakStrategy, 3>;
+
+    #[test]
+    fn picks_strategy_by_cost() {
+        let drops = AtomicUsize::new(0);
+
+        let small: Arc<Vec<Counted>, Strategy> =
+            Arc::new((0..2).map(|_| Counted(&drops)).collect());
+        drop(small);
+        assert_eq!(drops.load(SeqCst), 2);
+
+        let large: Arc<Vec<Counted>, Strategy> =
+            Arc::new((0..3).map(|_| Counted(&drops)).collect());
+        drop(large);
+        assert_eq!(drops.load(SeqCst), 2);
+    }
+
+    #[test]
+    fn header_slice_cost() {
+        let arc: Arc<HeaderSlice<u8, [String]>, Strategy> =
+            Arc::from_header_and_iter(1, (0..4).map(|i| i.to_string()));
+        assert_eq!(arc.drop_cost(), 4);
+
+        let arc: Arc<[u32], Strategy> = Arc::from(&[1, 2, 3, 4, 5][..]);
+        assert_eq!(arc.drop_cost(), 1);
+    }
+
+    #[test]
+    fn trivial_elements_cost_one() {
+        assert_eq!(vec![0u8; 1000].drop_cost(), 1);
+        assert_eq!("a long string".drop_cost(), 1);
+        assert_eq!(String::from("a long string").drop_cost(), 1);
+        assert_eq!(vec![String::new(); 3].drop_cost(), 3);
+    }
+}
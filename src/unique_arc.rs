@@ -1,8 +1,8 @@
-use alloc::vec::Vec;
-use alloc::{alloc::Layout, boxed::Box};
+use alloc::alloc::Layout;
+use allocator_api2::boxed::Box;
+use core::any::Any;
 use core::convert::TryFrom;
 use core::iter::FromIterator;
-use core::marker::PhantomData;
 use core::mem::{ManuallyDrop, MaybeUninit};
 use core::ops::{Deref, DerefMut};
 use core::ptr::{self, NonNull};
@@ -11,10 +11,14 @@ use core::sync::atomic::AtomicUsize;
 extern crate backdrop;
 use self::backdrop::BackdropStrategy;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::iterator_as_exact_size_iterator::IteratorAsExactSizeIterator;
-use crate::HeaderSlice;
+use crate::{ArcSliceBuilder, HeaderSlice};
 
-use super::{Arc, ArcInner};
+use super::{AllocError, Arc, ArcInner};
+use allocator_api2::alloc::{Allocator, Global};
 
 /// An `Arc` that is known to be uniquely owned
 ///
@@ -40,7 +44,9 @@ use super::{Arc, ArcInner};
 /// let y = x.shareable(); // y is an Arc<T, S>
 /// ```
 #[repr(transparent)]
-pub struct UniqueArc<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>>(Arc<T, S>);
+pub struct UniqueArc<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>, A>>, A: Allocator = Global>(
+    pub(crate) Arc<T, S, A>,
+);
 
 impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> UniqueArc<T, S> {
     #[inline]
@@ -49,19 +55,39 @@ impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> UniqueArc<T, S> {
         UniqueArc(Arc::new(data))
     }
 
+    /// Construct a new UniqueArc, returning an error if allocation fails.
+    #[inline]
+    pub fn try_new(data: T) -> Result<Self, AllocError> {
+        Arc::try_new(data).map(UniqueArc)
+    }
+}
+
+impl<T, S: BackdropStrategy<Box<ArcInner<T>, A>>, A: Allocator> UniqueArc<T, S, A> {
+    /// Construct a new UniqueArc in the given allocator
+    #[inline]
+    pub fn new_in(data: T, alloc: A) -> Self {
+        UniqueArc(Arc::new_in(data, alloc))
+    }
+
+    /// Construct a new UniqueArc in the given allocator, returning an error if allocation fails.
+    #[inline]
+    pub fn try_new_in(data: T, alloc: A) -> Result<Self, AllocError> {
+        Arc::try_new_in(data, alloc).map(UniqueArc)
+    }
+
     /// Gets the inner value of the unique arc
     pub fn into_inner(this: Self) -> T {
-        // Wrap the Arc in a `ManuallyDrop` so that its drop routine never runs
-        let this = ManuallyDrop::new(this.0);
         debug_assert!(
-            this.is_unique(),
+            this.0.is_unique(),
             "attempted to call `.into_inner()` on a `UniqueArc` with a non-zero ref count",
         );
+        // Take the allocator out of the Arc, so that its drop routine never runs
+        let (ptr, alloc) = Arc::into_raw_inner_with_allocator(this.0);
 
         // Safety: We have exclusive access to the inner data and the
         //         arc will not perform its drop routine since we've
-        //         wrapped it in a `ManuallyDrop`
-        unsafe { Box::from_raw(this.ptr()).data }
+        //         taken it apart
+        unsafe { Box::into_inner(Box::from_raw_in(ptr, alloc)).data }
     }
 }
 
@@ -69,30 +95,34 @@ impl<T, S: BackdropStrategy<Box<ArcInner<MaybeUninit<T>>>>> UniqueArc<MaybeUnini
     /// Construct an uninitialized arc
     #[inline]
     pub fn new_uninit() -> UniqueArc<MaybeUninit<T>, S> {
+        Self::try_new_uninit().unwrap_or_else(|_| {
+            alloc::alloc::handle_alloc_error(Layout::new::<ArcInner<MaybeUninit<T>>>())
+        })
+    }
+
+    /// Construct an uninitialized arc, returning an error if allocation fails.
+    #[inline]
+    pub fn try_new_uninit() -> Result<UniqueArc<MaybeUninit<T>, S>, AllocError> {
         unsafe {
             let layout = Layout::new::<ArcInner<MaybeUninit<T>>>();
             let ptr = alloc::alloc::alloc(layout);
             let mut p = NonNull::new(ptr)
-                .unwrap_or_else(|| alloc::alloc::handle_alloc_error(layout))
+                .ok_or(AllocError)?
                 .cast::<ArcInner<MaybeUninit<T>>>();
             ptr::write(&mut p.as_mut().count, AtomicUsize::new(1));
 
-            UniqueArc(Arc {
-                p,
-                phantom: PhantomData,
-                phantom_strategy: PhantomData,
-            })
+            Ok(UniqueArc(Arc::from_raw_inner(p.as_ptr())))
         }
     }
 }
 
-impl<T: ?Sized, S> UniqueArc<T, S>
+impl<T: ?Sized, S, A: Allocator> UniqueArc<T, S, A>
 where
-    S: BackdropStrategy<Box<ArcInner<T>>>,
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
 {
     /// Convert to a shareable Arc<T, S> once we're done mutating it
     #[inline]
-    pub fn shareable(self) -> Arc<T, S> {
+    pub fn shareable(self) -> Arc<T, S, A> {
         self.0
     }
 
@@ -104,7 +134,7 @@ where
     ///
     /// The given `Arc` must have a reference count of exactly one
     ///
-    pub(crate) unsafe fn from_arc(arc: Arc<T, S>) -> Self {
+    pub(crate) unsafe fn from_arc(arc: Arc<T, S, A>) -> Self {
         debug_assert_eq!(Arc::count(&arc), 1);
         Self(arc)
     }
@@ -116,12 +146,29 @@ where
     /// # Safety
     ///
     /// The given `Arc` must have a reference count of exactly one
-    pub(crate) unsafe fn from_arc_ref(arc: &mut Arc<T, S>) -> &mut Self {
-        debug_assert_eq!(Arc::count(&arc), 1);
+    pub(crate) unsafe fn from_arc_ref(arc: &mut Arc<T, S, A>) -> &mut Self {
+        debug_assert_eq!(Arc::count(arc), 1);
 
         // Safety: caller guarantees that `arc` is unique,
         //         `UniqueArc` is `repr(transparent)`
-        &mut *(arc as *mut Arc<T, S> as *mut UniqueArc<T, S>)
+        &mut *(arc as *mut Arc<T, S, A> as *mut UniqueArc<T, S, A>)
+    }
+}
+
+impl<S, A: Allocator> UniqueArc<dyn Any + Send + Sync, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<dyn Any + Send + Sync>, A>>,
+{
+    /// Attempts to downcast the `UniqueArc<dyn Any + Send + Sync, S>` to a concrete type.
+    ///
+    /// On failure, the original `UniqueArc` is returned.
+    #[inline]
+    pub fn downcast<T>(self) -> Result<UniqueArc<T, S, A>, Self>
+    where
+        T: Any + Send + Sync,
+        S: BackdropStrategy<Box<ArcInner<T>, A>>,
+    {
+        self.0.downcast().map(UniqueArc).map_err(UniqueArc)
     }
 }
 
@@ -158,11 +205,7 @@ where
     /// has actually been initialized before calling this method.
     #[inline]
     pub unsafe fn assume_init(this: Self) -> UniqueArc<T, S> {
-        UniqueArc(Arc {
-            p: ManuallyDrop::new(this).0.p.cast(),
-            phantom: PhantomData,
-            phantom_strategy: PhantomData,
-        })
+        UniqueArc(Arc::from_raw_inner(ManuallyDrop::new(this).0.ptr().cast()))
     }
 }
 
@@ -175,17 +218,41 @@ where
     /// Create an Arc contains an array `[MaybeUninit<T>]` of `len`.
     pub fn new_uninit_slice(len: usize) -> Self {
         let ptr: NonNull<ArcInner<HeaderSlice<(), [MaybeUninit<T>]>>> =
-            Arc::<_, S>::allocate_for_header_and_slice(len);
+            Arc::<_, S>::allocate_for_header_and_slice(len, &Global);
+
+        // Safety: `ptr` was just allocated for `len` elements
+        unsafe { Self::from_uninit_inner(ptr) }
+    }
+
+    /// Create an Arc contains an array `[MaybeUninit<T>]` of `len`,
+    /// returning an error if allocation fails.
+    ///
+    /// ```
+    /// use backdrop_arc::{TrivialStrategy, UniqueArc};
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let too_big = UniqueArc::<[MaybeUninit<u64>], TrivialStrategy>::try_new_uninit_slice(usize::MAX);
+    /// assert!(too_big.is_err());
+    /// ```
+    pub fn try_new_uninit_slice(len: usize) -> Result<Self, AllocError> {
+        let ptr: NonNull<ArcInner<HeaderSlice<(), [MaybeUninit<T>]>>> =
+            Arc::<_, S>::try_allocate_for_header_and_slice(len, &Global)?;
+
+        // Safety: `ptr` was just allocated for `len` elements
+        Ok(unsafe { Self::from_uninit_inner(ptr) })
+    }
 
+    /// # Safety
+    ///
+    /// `ptr` must be freshly allocated, with an initialized refcount.
+    unsafe fn from_uninit_inner(ptr: NonNull<ArcInner<HeaderSlice<(), [MaybeUninit<T>]>>>) -> Self {
         // Safety:
         // - `ArcInner` is properly allocated and initialized.
         //   - `()` and `[MaybeUninit<T>]` do not require special initialization
         // - The `Arc` is just created and so -- unique.
-        unsafe {
-            let arc: Arc<HeaderSlice<(), [MaybeUninit<T>]>, S> = Arc::from_raw_inner(ptr.as_ptr());
-            let arc: Arc<[MaybeUninit<T>], S> = arc.into();
-            UniqueArc(arc)
-        }
+        let arc: Arc<HeaderSlice<(), [MaybeUninit<T>]>, S> = Arc::from_raw_inner(ptr.as_ptr());
+        let arc: Arc<[MaybeUninit<T>], S> = arc.into();
+        UniqueArc(arc)
     }
 
     /// # Safety
@@ -197,10 +264,13 @@ where
     }
 }
 
-impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> TryFrom<Arc<T, S>> for UniqueArc<T, S> {
-    type Error = Arc<T, S>;
+impl<T: ?Sized, S, A: Allocator> TryFrom<Arc<T, S, A>> for UniqueArc<T, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
+{
+    type Error = Arc<T, S, A>;
 
-    fn try_from(arc: Arc<T, S>) -> Result<Self, Self::Error> {
+    fn try_from(arc: Arc<T, S, A>) -> Result<Self, Self::Error> {
         Arc::try_unique(arc)
     }
 }
@@ -209,33 +279,45 @@ impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> TryFrom<Arc<T, S>> for Un
 extern crate triomphe;
 
 #[cfg(feature = "triomphe")]
-/// Converting to- and from a [`triomphe::UniqueArc<T>`] is a zero-cost operation
+/// Converting to- and from a [`triomphe::UniqueArc<T>`] does not allocate or copy,
+/// since both use the same `ArcInner` layout.
 impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> From<triomphe::UniqueArc<T>> for UniqueArc<T, S> {
     #[inline]
     fn from(arc: triomphe::UniqueArc<T>) -> Self {
-        unsafe { core::mem::transmute(arc) }
+        // Safety: the `Arc` is still unique
+        unsafe { UniqueArc::from_arc(Arc::from(arc.shareable())) }
     }
 }
 
 #[cfg(feature = "triomphe")]
-/// Converting to- and from a [`triomphe::UniqueArc<T>`] is a zero-cost operation
+/// Converting to- and from a [`triomphe::UniqueArc<T>`] does not allocate or copy,
+/// since both use the same `ArcInner` layout.
 impl<T, S: BackdropStrategy<Box<ArcInner<T>>>> From<UniqueArc<T, S>> for triomphe::UniqueArc<T> {
     #[inline]
     fn from(arc: UniqueArc<T, S>) -> Self {
-        unsafe { core::mem::transmute(arc) }
+        match triomphe::Arc::try_unique(triomphe::Arc::from(arc.shareable())) {
+            Ok(unique) => unique,
+            Err(_) => unreachable!("the Arc of a UniqueArc is unique"),
+        }
     }
 }
 
-impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> Deref for UniqueArc<T, S> {
+impl<T: ?Sized, S, A: Allocator> Deref for UniqueArc<T, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
+{
     type Target = T;
 
     #[inline]
     fn deref(&self) -> &T {
-        &*self.0
+        &self.0
     }
 }
 
-impl<T: ?Sized, S: BackdropStrategy<Box<ArcInner<T>>>> DerefMut for UniqueArc<T, S> {
+impl<T: ?Sized, S, A: Allocator> DerefMut for UniqueArc<T, S, A>
+where
+    S: BackdropStrategy<Box<ArcInner<T>, A>>,
+{
     #[inline]
     fn deref_mut(&mut self) -> &mut T {
         // We know this to be uniquely owned
@@ -251,15 +333,46 @@ where
     fn from_iter<T: IntoIterator<Item = A>>(iter: T) -> Self {
         let iter = iter.into_iter();
         let (lower, upper) = iter.size_hint();
-        let arc: Arc<[A], S> = if Some(lower) == upper {
+        if Some(lower) == upper {
             let iter = IteratorAsExactSizeIterator::new(iter);
-            Arc::from_header_and_iter((), iter).into()
+            let arc: Arc<[A], S> = Arc::from_header_and_iter((), iter).into();
+            // Safety: We just created an `Arc`, so it's unique.
+            unsafe { UniqueArc::from_arc(arc) }
         } else {
-            let vec = iter.collect::<Vec<_>>();
-            Arc::from(vec)
-        };
-        // Safety: We just created an `Arc`, so it's unique.
-        unsafe { UniqueArc::from_arc(arc) }
+            // Grow the allocation in place rather than collecting into a `Vec` and copying
+            let mut builder = ArcSliceBuilder::with_capacity((), lower);
+            builder.extend(iter);
+            builder.finish_slice()
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: ?Sized, S> Deserialize<'de> for UniqueArc<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+    Arc<T, S>: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<UniqueArc<T, S>, D::Error>
+    where
+        D: ::serde::de::Deserializer<'de>,
+    {
+        let arc = Arc::<T, S>::deserialize(deserializer)?;
+        // Safety: Deserializing always creates a new `Arc`, so it's unique.
+        Ok(unsafe { UniqueArc::from_arc(arc) })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: ?Sized + Serialize, S> Serialize for UniqueArc<T, S>
+where
+    S: BackdropStrategy<Box<ArcInner<T>>>,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: ::serde::ser::Serializer,
+    {
+        (**self).serialize(serializer)
     }
 }
 
@@ -294,8 +407,43 @@ mod tests {
     use super::backdrop::TrivialStrategy;
 
     use crate::{Arc, UniqueArc};
+    use alloc::boxed::Box;
+    use core::any::Any;
     use core::{convert::TryFrom, mem::MaybeUninit};
 
+    #[test]
+    #[cfg(feature = "triomphe")]
+    fn triomphe_roundtrip() {
+        use super::triomphe;
+
+        let mut x: UniqueArc<u32, TrivialStrategy> = UniqueArc::new(1);
+        let ptr: *const u32 = &*x;
+        *x += 1;
+        let mut y = triomphe::UniqueArc::from(x);
+        *y += 1;
+        let z: UniqueArc<u32, TrivialStrategy> = UniqueArc::from(y);
+        assert_eq!(&*z as *const u32, ptr);
+        assert_eq!(*z, 3);
+    }
+
+    #[test]
+    fn downcast() {
+        let any: UniqueArc<dyn Any + Send + Sync, TrivialStrategy> =
+            UniqueArc::try_from(Arc::from(Box::new(7u32) as Box<dyn Any + Send + Sync>))
+                .ok()
+                .unwrap();
+        let any = match any.downcast::<u64>() {
+            Ok(_) => panic!("downcast to the wrong type succeeded"),
+            Err(any) => any,
+        };
+        let mut num = match any.downcast::<u32>() {
+            Ok(num) => num,
+            Err(_) => panic!("downcast to the right type failed"),
+        };
+        *num += 1;
+        assert_eq!(UniqueArc::into_inner(num), 8);
+    }
+
     #[test]
     fn unique_into_inner() {
         let unique = UniqueArc::<_, TrivialStrategy>::new(10u64);
@@ -0,0 +1,104 @@
+use allocator_api2::boxed::Box;
+use core::convert::TryFrom;
+use core::ops::{Deref, DerefMut};
+
+extern crate backdrop;
+use self::backdrop::BackdropStrategy;
+
+use super::{AllocError, Rc, RcInner};
+
+/// An `Rc` that is known to be uniquely owned
+///
+/// The single-threaded counterpart of [`UniqueArc`](crate::UniqueArc):
+/// it allows mutable access, and can be turned into a regular `Rc` using `.shareable()`.
+///
+/// ```rust
+/// # use backdrop_arc::{UniqueRc, TrivialStrategy};
+/// let mut x: UniqueRc<_, TrivialStrategy> = UniqueRc::new([1, 2, 3]);
+/// x[2] = 7; // mutate!
+/// let y = x.shareable(); // y is an Rc<T, S>
+/// ```
+#[repr(transparent)]
+pub struct UniqueRc<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>>(Rc<T, S>);
+
+impl<T, S: BackdropStrategy<Box<RcInner<T>>>> UniqueRc<T, S> {
+    /// Construct a new UniqueRc
+    #[inline]
+    pub fn new(data: T) -> Self {
+        UniqueRc(Rc::new(data))
+    }
+
+    /// Construct a new UniqueRc, returning an error if allocation fails.
+    #[inline]
+    pub fn try_new(data: T) -> Result<Self, AllocError> {
+        Rc::try_new(data).map(UniqueRc)
+    }
+
+    /// Gets the inner value of the unique rc
+    pub fn into_inner(this: Self) -> T {
+        debug_assert!(
+            this.0.is_unique(),
+            "attempted to call `.into_inner()` on a `UniqueRc` with a non-zero ref count",
+        );
+        // Safety: We have exclusive access to the inner data and the
+        //         rc will not perform its drop routine since we've
+        //         taken it apart
+        unsafe { Box::into_inner(Box::from_raw(Rc::into_raw_inner(this.0))).data }
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> UniqueRc<T, S> {
+    /// Convert to a shareable Rc<T, S> once we're done mutating it
+    #[inline]
+    pub fn shareable(self) -> Rc<T, S> {
+        self.0
+    }
+
+    /// Creates a new [`UniqueRc`] from the given [`Rc`].
+    ///
+    /// # Safety
+    ///
+    /// The given `Rc` must have a reference count of exactly one
+    pub(crate) unsafe fn from_rc(rc: Rc<T, S>) -> Self {
+        debug_assert_eq!(Rc::count(&rc), 1);
+        Self(rc)
+    }
+
+    /// Creates a new `&mut `[`UniqueRc`] from the given `&mut `[`Rc`].
+    ///
+    /// # Safety
+    ///
+    /// The given `Rc` must have a reference count of exactly one
+    pub(crate) unsafe fn from_rc_ref(rc: &mut Rc<T, S>) -> &mut Self {
+        debug_assert_eq!(Rc::count(rc), 1);
+
+        // Safety: caller guarantees that `rc` is unique,
+        //         `UniqueRc` is `repr(transparent)`
+        &mut *(rc as *mut Rc<T, S> as *mut UniqueRc<T, S>)
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> TryFrom<Rc<T, S>> for UniqueRc<T, S> {
+    type Error = Rc<T, S>;
+
+    fn try_from(rc: Rc<T, S>) -> Result<Self, Self::Error> {
+        Rc::try_unique(rc)
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> Deref for UniqueRc<T, S> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized, S: BackdropStrategy<Box<RcInner<T>>>> DerefMut for UniqueRc<T, S> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        // We know this to be uniquely owned
+        unsafe { &mut (*self.0.ptr()).data }
+    }
+}